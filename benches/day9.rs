@@ -0,0 +1,171 @@
+//! Compares the dense day9 heightmap with the `HashMap` one it replaced.
+
+use advent_2021::{day9, grid::Point};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::{HashMap, HashSet};
+
+/// The `HashMap` heightmap as it was before the dense grid.
+mod legacy {
+    use super::*;
+
+    type Coord = (isize, isize);
+    type Number = usize;
+
+    pub struct Input {
+        grid: HashMap<Coord, Number>,
+        height: isize,
+        width: isize,
+    }
+
+    pub fn input_parser(input: &str) -> Input {
+        let grid: HashMap<Coord, Number> = input
+            .lines()
+            .enumerate()
+            .flat_map(|(y, l)| {
+                l.chars()
+                    .enumerate()
+                    .map(|(x, c)| {
+                        (
+                            (x.try_into().unwrap(), y.try_into().unwrap()),
+                            Number::try_from(c.to_digit(10).unwrap()).unwrap(),
+                        )
+                    })
+                    .collect::<Vec<(Coord, Number)>>()
+            })
+            .collect();
+
+        let (max_x, max_y) = grid.keys().fold((0, 0), |(old_x, old_y), (x, y)| {
+            (old_x.max(*x), old_y.max(*y))
+        });
+
+        Input {
+            grid,
+            height: max_y + 1,
+            width: max_x + 1,
+        }
+    }
+
+    impl Input {
+        fn adjacent_cells(&self, x: isize, y: isize) -> Option<Vec<(Coord, Number)>> {
+            let offsets: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+            if x >= 0 && x < self.width && y >= 0 && y < self.height {
+                let cells = offsets
+                    .into_iter()
+                    .filter_map(|(offset_x, offset_y)| {
+                        let new_x = x + offset_x;
+                        let new_y = y + offset_y;
+                        self.grid.get(&(new_x, new_y)).map(|&n| ((new_x, new_y), n))
+                    })
+                    .collect();
+                Some(cells)
+            } else {
+                None
+            }
+        }
+
+        fn is_low_point(&self, x: isize, y: isize) -> Option<bool> {
+            self.grid.get(&(x, y)).map(|height| {
+                self.adjacent_cells(x, y)
+                    .unwrap()
+                    .iter()
+                    .all(|(_coord, other)| other > height)
+            })
+        }
+
+        pub fn low_points(&self) -> Vec<Coord> {
+            let mut low_points: Vec<Coord> = self
+                .grid
+                .keys()
+                .copied()
+                .filter(|&(x, y)| self.is_low_point(x, y) == Some(true))
+                .collect();
+            low_points.sort_unstable_by_key(|&(x, y)| (y, x));
+            low_points
+        }
+
+        pub fn basin_sizes(&self) -> Vec<usize> {
+            self.low_points()
+                .into_iter()
+                .map(|low_point| {
+                    let mut seen = HashSet::from([low_point]);
+                    let mut pending = vec![low_point];
+                    while let Some((x, y)) = pending.pop() {
+                        for (coord, height) in self.adjacent_cells(x, y).unwrap() {
+                            if height < 9 && seen.insert(coord) {
+                                pending.push(coord);
+                            }
+                        }
+                    }
+                    seen.len()
+                })
+                .collect()
+        }
+    }
+}
+
+/// A square heightmap like the puzzle input: walls of 9s split it into
+/// blocks, each sloping down to a single low point at a pseudo-random spot.
+fn heightmap(side: usize) -> String {
+    const BLOCK: usize = 12;
+    let mut state: u64 = 0x2021_0009;
+    let mut random = |below: usize| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        usize::try_from(state >> 33).unwrap() % below
+    };
+    let blocks = side.div_ceil(BLOCK);
+    let centres: Vec<(usize, usize)> = (0..blocks * blocks)
+        .map(|_| (1 + random(BLOCK - 1), 1 + random(BLOCK - 1)))
+        .collect();
+    (0..side)
+        .map(|y| {
+            (0..side)
+                .map(|x| {
+                    let (cx, cy) = centres[y / BLOCK * blocks + x / BLOCK];
+                    let (x, y) = (x % BLOCK, y % BLOCK);
+                    let height = if x == 0 || y == 0 {
+                        9
+                    } else {
+                        (x.abs_diff(cx) + y.abs_diff(cy)).min(8)
+                    };
+                    char::from(b'0' + u8::try_from(height).unwrap())
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn bench_day9(c: &mut Criterion) {
+    let raw = heightmap(1000);
+    let dense = day9::input_parser(&raw).unwrap();
+    let sparse = legacy::input_parser(&raw);
+    assert!(dense
+        .low_points()
+        .into_iter()
+        .eq(sparse.low_points().into_iter().map(Point::from)));
+    assert!(dense
+        .basins()
+        .iter()
+        .map(day9::Basin::size)
+        .eq(sparse.basin_sizes()));
+
+    let mut group = c.benchmark_group("day9");
+    group.sample_size(10);
+    group.bench_function("parse/dense", |b| {
+        b.iter(|| day9::input_parser(black_box(&raw)))
+    });
+    group.bench_function("parse/hashmap", |b| {
+        b.iter(|| legacy::input_parser(black_box(&raw)))
+    });
+    group.bench_function("low_points/dense", |b| b.iter(|| dense.low_points()));
+    group.bench_function("low_points/hashmap", |b| b.iter(|| sparse.low_points()));
+    group.bench_function("basins/dense", |b| b.iter(|| dense.basins()));
+    group.bench_function("basins/hashmap", |b| b.iter(|| sparse.basin_sizes()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_day9);
+criterion_main!(benches);
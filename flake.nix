@@ -5,8 +5,9 @@
     flake-utils.url = "github:numtide/flake-utils";
     master.url = "nixpkgs/master";
     nixpkgs.url = "nixpkgs/nixos-21.05";
-    rust-overlay.url = "github:oxalica/rust-overlay";
-    rust-overlay.inputs.flake-utils.follows = "flake-utils";
+    # Tracks master, since the toolchain must be at least the crate's
+    # rust-version.
+    rust-overlay.url = "github:oxalica/rust-overlay/master";
     rust-overlay.inputs.nixpkgs.follows = "unstable";
     unstable.url = "nixpkgs/nixos-unstable";
   };
@@ -20,7 +21,7 @@
           inherit system;
 
           overlays = [
-            inputs.rust-overlay.overlay
+            inputs.rust-overlay.overlays.default
             (final: prev: { inherit master unstable; })
           ];
         };
@@ -1,12 +1,24 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::num::ParseIntError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Bad depth on line {line}")]
+    BadDepth { line: usize, source: ParseIntError },
+}
 
 #[aoc_generator(day1)]
-pub fn input_generator(input: &str) -> Vec<usize> {
+pub fn input_generator(input: &str) -> Result<Vec<usize>, Error> {
     input
         .lines()
-        .map(str::parse::<usize>)
-        .collect::<Result<Vec<usize>, _>>()
-        .unwrap()
+        .enumerate()
+        .map(|(idx, l)| {
+            str::parse::<usize>(l).map_err(|source| Error::BadDepth {
+                line: idx + 1,
+                source,
+            })
+        })
+        .collect()
 }
 
 #[aoc(day1, part1)]
@@ -83,12 +83,8 @@ impl AddAssign<&Instruction> for CoordinatesWithAim {
 }
 
 #[aoc_generator(day2)]
-pub fn parse_instructions(input: &str) -> Vec<Instruction> {
-    input
-        .lines()
-        .map(Instruction::from_str)
-        .collect::<Result<Vec<Instruction>, Error>>()
-        .unwrap()
+pub fn parse_instructions(input: &str) -> Result<Vec<Instruction>, Error> {
+    input.lines().map(Instruction::from_str).collect()
 }
 
 #[aoc(day2, part1)]
@@ -1,15 +1,30 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, num::ParseIntError};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("No diagnostic numbers")]
+    Empty,
+    #[error("Bad diagnostic number on line {line}")]
+    BadNumber { line: usize, source: ParseIntError },
+}
+
 #[aoc_generator(day3)]
-pub fn parse_input(input: &str) -> (u8, Vec<u16>) {
+pub fn parse_input(input: &str) -> Result<(u8, Vec<u16>), Error> {
     let inputs = input
         .lines()
-        .map(|l| u16::from_str_radix(l, 2).unwrap())
-        .collect();
-    let width = input.lines().next().unwrap().len();
-    (width.try_into().unwrap(), inputs)
+        .enumerate()
+        .map(|(idx, l)| {
+            u16::from_str_radix(l, 2).map_err(|source| Error::BadNumber {
+                line: idx + 1,
+                source,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    // Every number parsed as sixteen bits or fewer, so the width fits.
+    let width = input.lines().next().ok_or(Error::Empty)?.len();
+    Ok((width.try_into().unwrap(), inputs))
 }
 
 fn count_digits(inputs: &[u16], width: u8) -> Vec<(u16, u16)> {
@@ -30,7 +45,7 @@ fn count_for_column(inputs: &[u16], column: u8) -> (u16, u16) {
 }
 
 #[aoc(day3, part1)]
-fn part_1((width, inputs): &(u8, Vec<u16>)) -> u32 {
+pub(crate) fn part_1((width, inputs): &(u8, Vec<u16>)) -> u32 {
     let counts = count_digits(inputs, *width);
 
     let mut gamma = 0;
@@ -47,7 +62,7 @@ fn part_1((width, inputs): &(u8, Vec<u16>)) -> u32 {
 }
 
 #[aoc(day3, part2)]
-fn part_2((width, inputs): &(u8, Vec<u16>)) -> u32 {
+pub(crate) fn part_2((width, inputs): &(u8, Vec<u16>)) -> u32 {
     let oxygen = filter_by_common(inputs, *width, Ordering::Greater, 1);
     let carbon = filter_by_common(inputs, *width, Ordering::Less, 0);
     (oxygen as u32) * (carbon as u32)
@@ -117,11 +132,24 @@ mod tests {
 "#
         .trim_start();
 
-        let (width, inputs) = parse_input(input);
+        let (width, inputs) = parse_input(input).unwrap();
 
         assert_eq!(part_2(&(width, inputs)), 230);
     }
 
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(parse_input(""), Err(Error::Empty)));
+        assert!(matches!(
+            parse_input("00100\n11120"),
+            Err(Error::BadNumber { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse_input("10000000000000000"),
+            Err(Error::BadNumber { line: 1, .. })
+        ));
+    }
+
     #[test]
     fn test_position() {
         fn has_zero_pos(n: u8, position: u8) -> bool {
@@ -1,64 +1,71 @@
+use crate::grid::{Grid, Point};
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    num::ParseIntError,
+};
 
 type Number = u8;
 type Drawings = Vec<Number>;
-type Coord = (usize, usize);
 
 const WIDTH: usize = 5;
 const HEIGHT: usize = 5;
 
-#[derive(Clone, Debug, Default)]
-struct Board {
-    positions: HashMap<Number, Coord>,
-    numbers: HashSet<Number>,
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("No numbers to draw")]
+    NoDrawings,
+    #[error("Bad number on line {line}")]
+    BadNumber { line: usize, source: ParseIntError },
+    #[error("The board starting on line {line} has {count} numbers instead of {}", WIDTH * HEIGHT)]
+    BoardSize { line: usize, count: usize },
+    #[error("No board wins")]
+    NoWinner,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Board {
+    numbers: Grid<Number>,
+    positions: HashMap<Number, Point>,
+    marked: Grid<bool>,
     last_drawn: Option<Number>,
-    drawn: HashSet<Number>,
-    drawn_coords: Vec<Coord>,
 }
 
 impl Board {
     fn new(cells: Vec<Number>) -> Self {
-        let positions = cells
-            .iter()
-            .enumerate()
-            .map(|(idx, n)| (*n, (idx.rem_euclid(WIDTH) + 1, idx.div_euclid(HEIGHT) + 1)))
-            .collect();
-        let numbers = cells.into_iter().collect();
+        let numbers = Grid::from_vec(WIDTH, cells).unwrap();
+        let positions = numbers.iter().map(|(point, &n)| (n, point)).collect();
         Self {
-            positions,
             numbers,
-            ..Default::default()
+            positions,
+            marked: Grid::new(WIDTH, HEIGHT, false),
+            last_drawn: None,
         }
     }
 
     #[cfg(test)]
     fn clear_draws(&mut self) {
-        self.drawn.clear();
-        self.drawn_coords.clear();
+        self.marked = Grid::new(WIDTH, HEIGHT, false);
     }
 
     fn draw(&mut self, n: Number) {
         let _old = self.last_drawn.insert(n);
-        if self.has_number(n) && self.drawn.insert(n) {
-            self.drawn_coords.push(self.coord_for_number(n).unwrap());
+        if let Some(point) = self.coord_for_number(n) {
+            self.marked[point] = true;
         }
     }
 
-    fn has_number(&self, n: Number) -> bool {
-        self.numbers.contains(&n)
-    }
-
-    fn coord_for_number(&self, n: Number) -> Option<Coord> {
+    fn coord_for_number(&self, n: Number) -> Option<Point> {
         self.positions.get(&n).copied()
     }
 
     fn score(&self) -> usize {
         let unscored: usize = self
             .numbers
-            .difference(&self.drawn)
-            .map(|&n| usize::from(n))
+            .iter()
+            .filter(|&(point, _n)| !self.marked[point])
+            .map(|(_point, &n)| usize::from(n))
             .sum();
         unscored
             .checked_mul(self.last_drawn.unwrap().into())
@@ -66,60 +73,63 @@ impl Board {
     }
 
     fn is_won(&self) -> bool {
-        if self.drawn.len() >= 5 {
-            self.forms_column() || self.forms_row()
-        } else {
-            false
-        }
+        self.forms_column() || self.forms_row()
     }
 
     fn forms_column(&self) -> bool {
-        self.drawn_coords
-            .iter()
-            .counts_by(|(x, _y)| x)
-            .iter()
-            .any(|(_col, count)| *count == 5)
+        self.marked
+            .columns()
+            .any(|mut column| column.all(|&marked| marked))
     }
 
     fn forms_row(&self) -> bool {
-        self.drawn_coords
-            .iter()
-            .counts_by(|(_x, y)| y)
-            .iter()
-            .any(|(_col, count)| *count == 5)
+        self.marked
+            .rows()
+            .any(|row| row.iter().all(|&marked| marked))
     }
 }
 
 #[aoc_generator(day4)]
-fn parse_boards_and_drawings(input: &str) -> (Drawings, Vec<Board>) {
-    let mut iter = input.lines();
+pub(crate) fn parse_boards_and_drawings(input: &str) -> Result<(Drawings, Vec<Board>), Error> {
+    let number = |line: usize| {
+        move |n: &str| str::parse::<Number>(n).map_err(|source| Error::BadNumber { line, source })
+    };
+    let mut iter = input.lines().enumerate();
 
     let drawings = iter
         .next()
-        .map(|l| l.split(',').map(|n| str::parse::<u8>(n).unwrap()).collect())
-        .unwrap();
+        .ok_or(Error::NoDrawings)?
+        .1
+        .split(',')
+        .map(number(1))
+        .collect::<Result<_, _>>()?;
 
     let boards = iter
         .chunks(6)
         .into_iter()
-        .map(|board_lines| {
-            let numbers: Vec<Number> = board_lines
-                .skip(1)
-                .flat_map(|l| {
-                    l.split_ascii_whitespace()
-                        .map(|n| str::parse::<u8>(n).unwrap())
-                        .collect::<Vec<u8>>()
-                })
-                .collect();
-            Board::new(numbers)
+        .map(|mut board_lines| {
+            let line = board_lines.next().map_or(0, |(idx, _blank)| idx + 2);
+            let mut numbers: Vec<Number> = Vec::new();
+            for (idx, l) in board_lines {
+                for n in l.split_ascii_whitespace() {
+                    numbers.push(number(idx + 1)(n)?);
+                }
+            }
+            if numbers.len() != WIDTH * HEIGHT {
+                return Err(Error::BoardSize {
+                    line,
+                    count: numbers.len(),
+                });
+            }
+            Ok(Board::new(numbers))
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
 
-    (drawings, boards)
+    Ok((drawings, boards))
 }
 
 #[aoc(day4, part1)]
-fn find_winning_score(input: &(Drawings, Vec<Board>)) -> usize {
+pub(crate) fn find_winning_score(input: &(Drawings, Vec<Board>)) -> Result<usize, Error> {
     let mut drawn_boards = input.1.clone();
 
     let mut winner: Option<Board> = None;
@@ -138,11 +148,11 @@ fn find_winning_score(input: &(Drawings, Vec<Board>)) -> usize {
         }
     }
 
-    winner.unwrap().score()
+    winner.map(|winner| winner.score()).ok_or(Error::NoWinner)
 }
 
 #[aoc(day4, part2)]
-fn find_last_winner(input: &(Drawings, Vec<Board>)) -> usize {
+pub(crate) fn find_last_winner(input: &(Drawings, Vec<Board>)) -> Result<usize, Error> {
     let mut drawn_boards = input.1.clone();
 
     let mut winner: Option<Board> = None;
@@ -163,7 +173,7 @@ fn find_last_winner(input: &(Drawings, Vec<Board>)) -> usize {
             });
     }
 
-    winner.unwrap().score()
+    winner.map(|winner| winner.score()).ok_or(Error::NoWinner)
 }
 
 #[cfg(test)]
@@ -174,10 +184,10 @@ mod tests {
     fn test_coord_for_number() {
         let board = Board::new((1..=25).collect());
 
-        assert_eq!(board.coord_for_number(1), Some((1, 1)));
-        assert_eq!(board.coord_for_number(6), Some((1, 2)));
-        assert_eq!(board.coord_for_number(15), Some((5, 3)));
-        assert_eq!(board.coord_for_number(25), Some((5, 5)));
+        assert_eq!(board.coord_for_number(1), Some(Point::new(0, 0)));
+        assert_eq!(board.coord_for_number(6), Some(Point::new(0, 1)));
+        assert_eq!(board.coord_for_number(15), Some(Point::new(4, 2)));
+        assert_eq!(board.coord_for_number(25), Some(Point::new(4, 4)));
         assert_eq!(board.coord_for_number(26), None);
     }
 
@@ -1,48 +1,33 @@
-use std::{
-    collections::HashMap,
-    ops::{Add, AddAssign},
-    str::FromStr,
-};
+use std::{collections::HashMap, num::ParseIntError, str::FromStr};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
-type Number = i32;
-#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-struct Point {
-    x: Number,
-    y: Number,
-}
-
-impl Add<Point> for Point {
-    type Output = Point;
+use crate::grid::{Grid, Point};
 
-    fn add(self, rhs: Point) -> Self::Output {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-        }
-    }
-}
-
-impl AddAssign<Point> for Point {
-    fn add_assign(&mut self, rhs: Point) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-    }
-}
+type Number = isize;
 
 #[derive(Clone, Copy, Debug)]
-struct Line {
+pub(crate) struct Line {
     p1: Point,
     p2: Point,
 }
 
 #[derive(Debug, thiserror::Error)]
-enum Error {
-    #[error("Missing one or both points")]
-    MissingPoint,
-    #[error("Malformed line")]
-    Malformed,
+pub(crate) enum Error {
+    #[error("Missing one or both points at line {line}, column {column}")]
+    MissingPoint { line: usize, column: usize },
+    #[error("Malformed line at line {line}, column {column}")]
+    Malformed { line: usize, column: usize },
+    #[error("Bad integer at line {line}, column {column}: {source}")]
+    BadInteger {
+        line: usize,
+        column: usize,
+        source: ParseIntError,
+    },
+    #[error("Unexpected extra component at line {line}, column {column}")]
+    ExtraComponent { line: usize, column: usize },
+    #[error("Unexpected whitespace at line {line}, column {column}")]
+    Whitespace { line: usize, column: usize },
 }
 
 enum Slope {
@@ -103,10 +88,10 @@ impl Iterator for LinePoints {
             None
         } else {
             let delta = match self.slope {
-                Slope::Horizontal => Point { x: 1, y: 0 },
-                Slope::Vertical => Point { x: 0, y: 1 },
-                Slope::NorthwestSoutheast => Point { x: 1, y: -1 },
-                Slope::SouthwestNortheast => Point { x: 1, y: 1 },
+                Slope::Horizontal => Point::new(1, 0),
+                Slope::Vertical => Point::new(0, 1),
+                Slope::NorthwestSoutheast => Point::new(1, -1),
+                Slope::SouthwestNortheast => Point::new(1, 1),
             };
             let emit = self.next;
             self.reached = self.next == self.target;
@@ -116,16 +101,60 @@ impl Iterator for LinePoints {
     }
 }
 
-impl FromStr for Point {
-    type Err = Error;
+/// Splits `s` on `separator`, pairing each piece with the 1-based column it
+/// starts at, given that `s` itself starts at `column`.
+fn components<'a>(
+    s: &'a str,
+    separator: &'a str,
+    column: usize,
+) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+    s.split(separator).scan(column, move |next, piece| {
+        let start = *next;
+        *next += piece.len() + separator.len();
+        Some((start, piece))
+    })
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match &s.split(',').collect::<Vec<&str>>()[..] {
-            [x, y] => Ok(Self {
-                x: Number::from_str(x).unwrap(),
-                y: Number::from_str(y).unwrap(),
+fn parse_number(s: &str, line: usize, column: usize) -> Result<Number, Error> {
+    if let Some(offset) = s.find(char::is_whitespace) {
+        return Err(Error::Whitespace {
+            line,
+            column: column + offset,
+        });
+    }
+    Number::from_str(s).map_err(|source| Error::BadInteger {
+        line,
+        column,
+        source,
+    })
+}
+
+fn parse_point(s: &str, line: usize, column: usize) -> Result<Point, Error> {
+    match components(s, ",", column).collect::<Vec<_>>()[..] {
+        [(x_column, x), (y_column, y)] => Ok(Point::new(
+            parse_number(x, line, x_column)?,
+            parse_number(y, line, y_column)?,
+        )),
+        [_, _, (extra, _), ..] => Err(Error::ExtraComponent {
+            line,
+            column: extra,
+        }),
+        _ => Err(Error::MissingPoint { line, column }),
+    }
+}
+
+impl Line {
+    fn parse(s: &str, line: usize) -> Result<Self, Error> {
+        match components(s, " -> ", 1).collect::<Vec<_>>()[..] {
+            [(p1_column, p1), (p2_column, p2)] => Ok(Self {
+                p1: parse_point(p1, line, p1_column)?,
+                p2: parse_point(p2, line, p2_column)?,
+            }),
+            [_, _, (extra, _), ..] => Err(Error::ExtraComponent {
+                line,
+                column: extra,
             }),
-            _ => Err(Error::MissingPoint),
+            _ => Err(Error::Malformed { line, column: 1 }),
         }
     }
 }
@@ -134,12 +163,35 @@ impl FromStr for Line {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match &s.split(" -> ").collect::<Vec<&str>>()[..] {
-            [p1, p2] => Ok(Self {
-                p1: Point::from_str(p1)?,
-                p2: Point::from_str(p2)?,
-            }),
-            _ => Err(Error::Malformed),
+        Self::parse(s, 1)
+    }
+}
+
+/// Bounding boxes up to this many cells are counted in a dense grid; larger
+/// ones, such as a single long diagonal, fall back to a sparse map.
+const MAX_DENSE_CELLS: usize = 1 << 22;
+
+/// How many lines cross each point.
+#[derive(Debug)]
+enum Counts {
+    /// Every point of the bounding box, offset by its corner.
+    Dense { origin: Point, grid: Grid<usize> },
+    /// Only the points some line crosses.
+    Sparse(HashMap<Point, usize>),
+}
+
+impl Counts {
+    fn add(&mut self, point: Point) {
+        match self {
+            Counts::Dense { origin, grid } => grid[point - *origin] += 1,
+            Counts::Sparse(map) => *map.entry(point).or_default() += 1,
+        }
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        match self {
+            Counts::Dense { grid, .. } => Box::new(grid.values().copied()),
+            Counts::Sparse(map) => Box::new(map.values().copied()),
         }
     }
 }
@@ -147,26 +199,32 @@ impl FromStr for Line {
 #[derive(Debug)]
 struct Map {
     lines: Vec<Line>,
-    height: Number,
-    width: Number,
-    coordinates: HashMap<Point, usize>,
+    coordinates: Counts,
 }
 
 impl Map {
     fn new(lines: Vec<Line>) -> Self {
-        let (height, width) = lines.iter().fold((0, 0), |(x, y), line| {
-            (
-                x.max(line.p1.x).max(line.p2.x),
-                y.max(line.p1.y).max(line.p2.y),
-            )
-        });
+        let points = || lines.iter().flat_map(|line| [line.p1, line.p2]);
+        let origin = Point::new(
+            points().map(|p| p.x).min().unwrap_or(0),
+            points().map(|p| p.y).min().unwrap_or(0),
+        );
+        let far = Point::new(
+            points().map(|p| p.x).max().unwrap_or(0),
+            points().map(|p| p.y).max().unwrap_or(0),
+        );
+        let size = far - origin + Point::new(1, 1);
+        let width = usize::try_from(size.x).unwrap();
+        let height = usize::try_from(size.y).unwrap();
 
-        Self {
-            height,
-            lines,
-            width,
-            coordinates: HashMap::with_capacity((height * width).try_into().unwrap()),
-        }
+        let coordinates = match width.checked_mul(height) {
+            Some(cells) if cells <= MAX_DENSE_CELLS => Counts::Dense {
+                origin,
+                grid: Grid::new(width, height, 0),
+            },
+            _ => Counts::Sparse(HashMap::new()),
+        };
+        Self { lines, coordinates }
     }
 
     fn map_lines(&mut self, problem_part: ProblemPart) {
@@ -175,38 +233,37 @@ impl Map {
             _ => problem_part != ProblemPart::Part1,
         }) {
             for point in line.points() {
-                let count = self.coordinates.entry(point).or_insert(0);
-                *count += 1;
+                self.coordinates.add(point);
             }
         }
     }
 
     fn points_with_min_count(&self, min_count: usize) -> usize {
         self.coordinates
-            .iter()
-            .filter(|(_point, count)| **count >= min_count)
+            .values()
+            .filter(|&count| count >= min_count)
             .count()
     }
 }
 
 #[aoc_generator(day5)]
-fn input_generator(input: &str) -> Vec<Line> {
+pub(crate) fn input_generator(input: &str) -> Result<Vec<Line>, Error> {
     input
         .lines()
-        .map(Line::from_str)
-        .collect::<Result<Vec<_>, _>>()
-        .unwrap()
+        .enumerate()
+        .map(|(idx, l)| Line::parse(l, idx + 1))
+        .collect()
 }
 
 #[aoc(day5, part1)]
-fn part1(input: &[Line]) -> usize {
+pub(crate) fn part1(input: &[Line]) -> usize {
     let mut map = Map::new(input.to_vec());
     map.map_lines(ProblemPart::Part1);
     map.points_with_min_count(2)
 }
 
 #[aoc(day5, part2)]
-fn part2(input: &[Line]) -> usize {
+pub(crate) fn part2(input: &[Line]) -> usize {
     let mut map = Map::new(input.to_vec());
     map.map_lines(ProblemPart::Part2);
     map.points_with_min_count(2)
@@ -219,16 +276,61 @@ mod tests {
     #[test]
     fn test_line_points() {
         let line = Line {
-            p1: Point { x: 1, y: 1 },
-            p2: Point { x: 3, y: 3 },
+            p1: Point::new(1, 1),
+            p2: Point::new(3, 3),
         };
         assert_eq!(
             line.points().collect::<Vec<Point>>(),
-            vec![
-                Point { x: 1, y: 1 },
-                Point { x: 2, y: 2 },
-                Point { x: 3, y: 3 },
-            ]
+            vec![Point::new(1, 1), Point::new(2, 2), Point::new(3, 3)]
         )
     }
+
+    #[test]
+    fn test_sparse_map() {
+        let lines = input_generator("0,0 -> 30000,30000\n0,30000 -> 30000,0").unwrap();
+        assert_eq!(part1(&lines), 0);
+        assert_eq!(part2(&lines), 1);
+    }
+
+    #[test]
+    fn test_input_generator() {
+        let lines = input_generator("0,9 -> 5,9\n8,0 -> 0,8\n").unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].p2, Point::new(0, 8));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            input_generator("0,9 -> 5,9\n8,x -> 0,8"),
+            Err(Error::BadInteger {
+                line: 2,
+                column: 3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            input_generator("0,9,1 -> 5,9"),
+            Err(Error::ExtraComponent { line: 1, column: 5 })
+        ));
+        assert!(matches!(
+            input_generator("0,9 -> 5,9 -> 1,1"),
+            Err(Error::ExtraComponent {
+                line: 1,
+                column: 15
+            })
+        ));
+        assert!(matches!(
+            input_generator("0,9  -> 5,9"),
+            Err(Error::Whitespace { line: 1, column: 4 })
+        ));
+        assert!(matches!(
+            input_generator("0,9 -> 5"),
+            Err(Error::MissingPoint { line: 1, column: 8 })
+        ));
+        assert!(matches!(
+            input_generator("0,9 5,9"),
+            Err(Error::Malformed { line: 1, column: 1 })
+        ));
+    }
 }
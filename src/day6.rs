@@ -1,53 +1,894 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use num_bigint::BigUint;
+use std::{
+    io::{self, Write},
+    num::NonZeroU64,
+};
 
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Population count overflowed")]
+    Overflow,
+    #[error("Spawn interval must be at least one day")]
+    ZeroSpawnInterval,
+    #[error("Mortality age must be at least one day")]
+    ZeroMortalityAge,
+    #[error(
+        "A school of this species would need {states} states, more than the {MAX_STATES} allowed"
+    )]
+    TooManyStates { states: usize },
+    #[error("Timer {0} is out of range for this species")]
+    TimerOutOfRange(u8),
+    #[error("Bad token {token:?} at line {line}, column {column}")]
+    BadToken {
+        token: String,
+        line: usize,
+        column: usize,
+    },
+    #[error("Timer {timer} at line {line}, column {column} is out of range for this species")]
+    TimerOutOfRangeAt {
+        timer: u64,
+        line: usize,
+        column: usize,
+    },
+}
+
+/// The most states a school may track. Projections multiply matrices with a
+/// row and a column per state, so this bounds their size and cost.
+pub const MAX_STATES: usize = 1 << 10;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Lanternfish(pub u8);
+
+/// Parameters of a species' life cycle, all measured in days.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LifecycleSpec {
+    /// Days between spawns once a fish is mature.
+    pub spawn_interval: u32,
+    /// Extra days a newborn waits before its first spawn cycle begins.
+    pub maturation_delay: u32,
+    /// Newborns produced by each spawn.
+    pub offspring: u32,
+    /// Age at which a fish dies, if it dies at all.
+    pub mortality_age: Option<u32>,
+}
+
+/// Population of a school on one day of a simulation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub day: usize,
+    pub total: u128,
+    /// Fish per timer value, indexed by timer.
+    pub buckets: Vec<u128>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeSeries(pub Vec<Snapshot>);
+
+impl TimeSeries {
+    /// Writes one row per day, with the total followed by each timer bucket.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let timers = self.0.first().map_or(0, |snapshot| snapshot.buckets.len());
+        write!(writer, "day,total")?;
+        for timer in 0..timers {
+            write!(writer, ",timer_{}", timer)?;
+        }
+        writeln!(writer)?;
+
+        for snapshot in &self.0 {
+            write!(writer, "{},{}", snapshot.day, snapshot.total)?;
+            for n in &snapshot.buckets {
+                write!(writer, ",{}", n)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LanternfishSchool {
+    spec: LifecycleSpec,
+    transition: Matrix<usize>,
+    counts: Vec<usize>,
+}
+
+/// Arithmetic used to accumulate population counts when projecting a school.
+pub trait Counter {
+    type Count: Clone;
+
+    fn count(&self, n: usize) -> Self::Count;
+    fn add(&self, a: &Self::Count, b: &Self::Count) -> Option<Self::Count>;
+    fn mul(&self, a: &Self::Count, b: &Self::Count) -> Option<Self::Count>;
+}
+
+/// Counts in `usize`, failing on overflow.
 #[derive(Clone, Copy, Debug)]
-struct Lanternfish(u8);
+pub struct Checked;
+
+/// Counts in `u128`, failing on overflow.
+#[derive(Clone, Copy, Debug)]
+pub struct Wide;
+
+/// Counts with arbitrary precision, which never overflows.
+#[derive(Clone, Copy, Debug)]
+pub struct Arbitrary;
+
+/// Counts modulo the given value.
+#[derive(Clone, Copy, Debug)]
+pub struct Modulo(pub NonZeroU64);
+
+impl Counter for Checked {
+    type Count = usize;
+
+    fn count(&self, n: usize) -> usize {
+        n
+    }
+
+    fn add(&self, a: &usize, b: &usize) -> Option<usize> {
+        a.checked_add(*b)
+    }
+
+    fn mul(&self, a: &usize, b: &usize) -> Option<usize> {
+        a.checked_mul(*b)
+    }
+}
+
+impl Counter for Wide {
+    type Count = u128;
+
+    fn count(&self, n: usize) -> u128 {
+        n as u128
+    }
+
+    fn add(&self, a: &u128, b: &u128) -> Option<u128> {
+        a.checked_add(*b)
+    }
+
+    fn mul(&self, a: &u128, b: &u128) -> Option<u128> {
+        a.checked_mul(*b)
+    }
+}
 
+impl Counter for Arbitrary {
+    type Count = BigUint;
+
+    fn count(&self, n: usize) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> Option<BigUint> {
+        Some(a + b)
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> Option<BigUint> {
+        Some(a * b)
+    }
+}
+
+impl Counter for Modulo {
+    type Count = u64;
+
+    fn count(&self, n: usize) -> u64 {
+        (n as u128 % u128::from(self.0.get())) as u64
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> Option<u64> {
+        Some(((u128::from(*a) + u128::from(*b)) % u128::from(self.0.get())) as u64)
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> Option<u64> {
+        Some(((u128::from(*a) * u128::from(*b)) % u128::from(self.0.get())) as u64)
+    }
+}
+
+/// Square matrix of counts, stored row-major.
 #[derive(Clone, Debug)]
-struct LanternfishSchool([usize; 9]);
+struct Matrix<T>(Vec<Vec<T>>);
+
+impl<T: Clone> Matrix<T> {
+    fn identity<C: Counter<Count = T>>(counter: &C, size: usize) -> Self {
+        Self(
+            (0..size)
+                .map(|row| {
+                    (0..size)
+                        .map(|col| counter.count(usize::from(row == col)))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn multiply<C: Counter<Count = T>>(&self, rhs: &Self, counter: &C) -> Option<Self> {
+        let size = self.0.len();
+        let rows = (0..size)
+            .map(|row| {
+                (0..size)
+                    .map(|col| {
+                        (0..size).try_fold(counter.count(0), |sum, k| {
+                            counter.add(&sum, &counter.mul(&self.0[row][k], &rhs.0[k][col])?)
+                        })
+                    })
+                    .collect::<Option<Vec<T>>>()
+            })
+            .collect::<Option<Vec<Vec<T>>>>()?;
+        Some(Self(rows))
+    }
+
+    fn apply<C: Counter<Count = T>>(&self, counts: &[T], counter: &C) -> Option<Vec<T>> {
+        self.0
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(counts)
+                    .try_fold(counter.count(0), |sum, (factor, n)| {
+                        counter.add(&sum, &counter.mul(factor, n)?)
+                    })
+            })
+            .collect()
+    }
+
+    fn pow<C: Counter<Count = T>>(&self, mut exponent: u64, counter: &C) -> Option<Self> {
+        let mut result = Self::identity(counter, self.0.len());
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base, counter)?;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.multiply(&base, counter)?;
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Matrix<usize> {
+    fn convert<C: Counter>(&self, counter: &C) -> Matrix<C::Count> {
+        Matrix(
+            self.0
+                .iter()
+                .map(|row| row.iter().map(|&n| counter.count(n)).collect())
+                .collect(),
+        )
+    }
+}
+
+impl LifecycleSpec {
+    /// The species from the puzzle: a seven day cycle, with two extra days
+    /// before a newborn's first cycle and no natural death.
+    pub const LANTERNFISH: Self = Self {
+        spawn_interval: 7,
+        maturation_delay: 2,
+        offspring: 1,
+        mortality_age: None,
+    };
+
+    /// A species with the given life cycle, as long as a school of it can be
+    /// simulated.
+    pub fn new(
+        spawn_interval: u32,
+        maturation_delay: u32,
+        offspring: u32,
+        mortality_age: Option<u32>,
+    ) -> Result<Self, Error> {
+        let spec = Self {
+            spawn_interval,
+            maturation_delay,
+            offspring,
+            mortality_age,
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Checks that a school of this species can be simulated, which the
+    /// public fields alone don't ensure.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.spawn_interval == 0 {
+            Err(Error::ZeroSpawnInterval)
+        } else if self.mortality_age == Some(0) {
+            Err(Error::ZeroMortalityAge)
+        } else if self.states() > MAX_STATES {
+            Err(Error::TooManyStates {
+                states: self.states(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Timer of a newborn, and the largest timer any fish can have.
+    pub fn newborn_timer(&self) -> usize {
+        // Widened so that no `u32` fields overflow, and saturating so that
+        // an invalid zero interval doesn't either.
+        (self.spawn_interval as usize).saturating_sub(1) + self.maturation_delay as usize
+    }
+
+    fn timer_at_age(&self, age: usize) -> usize {
+        let newborn = self.newborn_timer();
+        if age <= newborn {
+            newborn - age
+        } else {
+            (newborn as isize - age as isize).rem_euclid(self.spawn_interval as isize) as usize
+        }
+    }
+
+    /// Number of states a school tracks. Without a mortality age, fish are
+    /// only distinguished by timer. With one, they must be tracked by age.
+    fn states(&self) -> usize {
+        match self.mortality_age {
+            None => self.newborn_timer() + 1,
+            Some(age) => age as usize,
+        }
+    }
+
+    /// State of a fish with the given timer. Under a mortality age, fish are
+    /// assumed to be in their first cycle, which is `None` if they would
+    /// already be dead.
+    fn state_for_timer(&self, timer: usize) -> Option<usize> {
+        match self.mortality_age {
+            None => Some(timer),
+            Some(_) => Some(self.newborn_timer() - timer).filter(|&age| age < self.states()),
+        }
+    }
+
+    /// Timer of every fish in the given state.
+    fn timer_for_state(&self, state: usize) -> usize {
+        match self.mortality_age {
+            None => state,
+            Some(_) => self.timer_at_age(state),
+        }
+    }
+
+    /// Long-run factor by which the population grows each day, which is the
+    /// dominant eigenvalue of the daily transition.
+    pub fn growth_rate(&self) -> f64 {
+        // Power iteration converges on the transition plus the identity even
+        // when the transition alone is periodic, as with a single age class.
+        let transition = self.transition();
+        let states = transition.0.len();
+        let mut vector = vec![1.0 / states as f64; states];
+        let mut rate = 0.0;
+
+        for _iteration in 0..100_000 {
+            let next: Vec<f64> = transition
+                .0
+                .iter()
+                .zip(&vector)
+                .map(|(row, own)| {
+                    own + row
+                        .iter()
+                        .zip(&vector)
+                        .map(|(&factor, n)| factor as f64 * n)
+                        .sum::<f64>()
+                })
+                .collect();
+            let total: f64 = next.iter().sum();
+            vector = next.into_iter().map(|n| n / total).collect();
+
+            let converged = (total - 1.0 - rate).abs() < 1e-12;
+            rate = total - 1.0;
+            if converged {
+                break;
+            }
+        }
+
+        rate
+    }
+
+    /// One day of simulation as a matrix, where entry `[to][from]` is the
+    /// number of fish in state `to` produced by one fish in state `from`.
+    fn transition(&self) -> Matrix<usize> {
+        let states = self.states();
+        let offspring = self.offspring as usize;
+        let mut rows = vec![vec![0usize; states]; states];
+
+        match self.mortality_age {
+            None => {
+                for timer in 1..states {
+                    rows[timer - 1][timer] += 1;
+                }
+                rows[self.spawn_interval as usize - 1][0] += 1;
+                rows[self.newborn_timer()][0] += offspring;
+            }
+            Some(_) => {
+                for age in 0..states {
+                    if age + 1 < states {
+                        rows[age + 1][age] += 1;
+                    }
+                    if self.timer_at_age(age) == 0 {
+                        rows[0][age] += offspring;
+                    }
+                }
+            }
+        }
+
+        Matrix(rows)
+    }
+}
+
+impl Default for LifecycleSpec {
+    fn default() -> Self {
+        Self::LANTERNFISH
+    }
+}
 
 impl LanternfishSchool {
-    fn new(fish: &[Lanternfish]) -> Self {
-        let mut counts = [0usize; 9];
-        fish.iter().for_each(|fish| counts[usize::from(fish.0)] += 1);
-        Self(counts)
+    pub fn new(fish: &[Lanternfish]) -> Result<Self, Error> {
+        Self::with_spec(fish, LifecycleSpec::LANTERNFISH)
+    }
+
+    pub fn with_spec(fish: &[Lanternfish], spec: LifecycleSpec) -> Result<Self, Error> {
+        spec.validate()?;
+
+        let mut counts = vec![0usize; spec.states()];
+        for fish in fish {
+            let state = Some(usize::from(fish.0))
+                .filter(|&timer| timer <= spec.newborn_timer())
+                .and_then(|timer| spec.state_for_timer(timer))
+                .ok_or(Error::TimerOutOfRange(fish.0))?;
+            counts[state] += 1;
+        }
+
+        Ok(Self {
+            spec,
+            transition: spec.transition(),
+            counts,
+        })
+    }
+
+    pub fn spec(&self) -> &LifecycleSpec {
+        &self.spec
+    }
+
+    fn simulate_day(&mut self) -> Result<(), Error> {
+        self.counts = self
+            .transition
+            .apply(&self.counts, &Checked)
+            .ok_or(Error::Overflow)?;
+        Ok(())
+    }
+
+    pub fn count(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Fish per timer value, indexed by timer.
+    pub fn buckets(&self) -> Vec<usize> {
+        let mut buckets = vec![0; self.spec.newborn_timer() + 1];
+        for (state, n) in self.counts.iter().enumerate() {
+            buckets[self.spec.timer_for_state(state)] += n;
+        }
+        buckets
     }
 
-    fn simulate_day(&mut self) {
-        let new = self.0[0];
+    /// Records the population on every day from today until `days` from now.
+    pub fn series(&self, days: usize) -> Result<TimeSeries, Error> {
+        let mut counts: Vec<u128> = self.counts.iter().map(|&n| Wide.count(n)).collect();
+        let transition = self.transition.convert(&Wide);
+        let mut snapshots = Vec::with_capacity(days + 1);
 
-        for idx in 1..=8 {
-            self.0[idx - 1] = self.0[idx];
+        for day in 0..=days {
+            if day > 0 {
+                counts = transition.apply(&counts, &Wide).ok_or(Error::Overflow)?;
+            }
+            let mut buckets = vec![0u128; self.spec.newborn_timer() + 1];
+            for (state, n) in counts.iter().enumerate() {
+                let bucket = &mut buckets[self.spec.timer_for_state(state)];
+                *bucket = bucket.checked_add(*n).ok_or(Error::Overflow)?;
+            }
+            snapshots.push(Snapshot {
+                day,
+                total: buckets
+                    .iter()
+                    .try_fold(0u128, |sum, n| sum.checked_add(*n))
+                    .ok_or(Error::Overflow)?,
+                buckets,
+            });
         }
 
-        self.0[6] += new;
-        self.0[8] = new;
+        Ok(TimeSeries(snapshots))
     }
 
-    fn count(&self) -> usize {
-        self.0.iter().sum()
+    /// First day, counting today as zero, on which the population exceeds
+    /// `threshold`, if that happens within `max_days`.
+    pub fn first_day_exceeding(
+        &self,
+        threshold: u128,
+        max_days: usize,
+    ) -> Result<Option<usize>, Error> {
+        let mut counts: Vec<u128> = self.counts.iter().map(|&n| Wide.count(n)).collect();
+        let transition = self.transition.convert(&Wide);
+
+        for day in 0..=max_days {
+            if day > 0 {
+                counts = transition.apply(&counts, &Wide).ok_or(Error::Overflow)?;
+            }
+            let total = counts
+                .iter()
+                .try_fold(0u128, |sum, n| sum.checked_add(*n))
+                .ok_or(Error::Overflow)?;
+            if total > threshold {
+                return Ok(Some(day));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Projects the total population `days` into the future without
+    /// simulating each day, using `counter` for the arithmetic.
+    pub fn project<C: Counter>(&self, days: u64, counter: &C) -> Result<C::Count, Error> {
+        let projection = self
+            .transition
+            .convert(counter)
+            .pow(days, counter)
+            .ok_or(Error::Overflow)?;
+
+        projection
+            .0
+            .iter()
+            .flat_map(|row| row.iter().zip(self.counts.iter()))
+            .try_fold(counter.count(0), |sum, (factor, &fish)| {
+                counter.add(&sum, &counter.mul(factor, &counter.count(fish))?)
+            })
+            .ok_or(Error::Overflow)
     }
 }
 
 #[aoc_generator(day6)]
-fn input_generator(input: &str) -> Vec<Lanternfish> {
-   input.split(',').map(|n| Lanternfish(str::parse::<u8>(n).unwrap())).collect()
+pub fn input_generator(input: &str) -> Result<Vec<Lanternfish>, Error> {
+    parse_timers(input, &LifecycleSpec::LANTERNFISH)
+}
+
+/// Parses the timers of a school of the given species, rejecting any timer
+/// no fish of it can have.
+pub fn parse_timers(input: &str, spec: &LifecycleSpec) -> Result<Vec<Lanternfish>, Error> {
+    spec.validate()?;
+    let max_timer = spec.newborn_timer() as u64;
+
+    input
+        .lines()
+        .enumerate()
+        .flat_map(|(idx, l)| {
+            tokens(l)
+                .into_iter()
+                .map(move |(column, token)| (idx + 1, column, token))
+        })
+        .map(|(line, column, token)| {
+            let timer = str::parse::<u64>(token).map_err(|_| Error::BadToken {
+                token: token.to_string(),
+                line,
+                column,
+            })?;
+            u8::try_from(timer)
+                .ok()
+                .filter(|&timer| u64::from(timer) <= max_timer)
+                .map(Lanternfish)
+                .ok_or(Error::TimerOutOfRangeAt {
+                    timer,
+                    line,
+                    column,
+                })
+        })
+        .collect()
+}
+
+/// Splits a line into comma separated fields, each holding one or more
+/// whitespace separated tokens, and pairs each token with the 1-based column
+/// it starts at. A field with no token in it, whether first, last or between
+/// two commas, is returned as an empty token.
+fn tokens(line: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+
+    for field in line.split(',') {
+        let before = tokens.len();
+        let mut rest = field;
+        while let Some(offset) = rest.find(|c: char| !c.is_whitespace()) {
+            rest = &rest[offset..];
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            tokens.push((start + field.len() - rest.len() + 1, &rest[..end]));
+            rest = &rest[end..];
+        }
+        if tokens.len() == before && line.contains(',') {
+            tokens.push((start + 1, ""));
+        }
+        start += field.len() + 1;
+    }
+
+    tokens
 }
 
 #[aoc(day6, part1)]
-fn part1(input: &[Lanternfish]) -> usize {
-    let mut school = LanternfishSchool::new(input);
+pub(crate) fn part1(input: &[Lanternfish]) -> Result<usize, Error> {
+    let mut school = LanternfishSchool::new(input)?;
     for _day in 1..=80 {
-        school.simulate_day();
+        school.simulate_day()?;
     }
-    school.count()
+    Ok(school.count())
 }
 
 #[aoc(day6, part2)]
-fn part2(input: &[Lanternfish]) -> usize {
-    let mut school = LanternfishSchool::new(input);
+pub(crate) fn part2(input: &[Lanternfish]) -> Result<usize, Error> {
+    let mut school = LanternfishSchool::new(input)?;
     for _day in 1..=256 {
-        school.simulate_day();
+        school.simulate_day()?;
+    }
+    Ok(school.count())
+}
+
+#[aoc(day6, part2, matrix)]
+pub(crate) fn part2_matrix(input: &[Lanternfish]) -> Result<usize, Error> {
+    LanternfishSchool::new(input)?.project(256, &Checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project() {
+        let school = LanternfishSchool::new(&input_generator("3,4,3,1,2").unwrap()).unwrap();
+
+        assert_eq!(school.project(0, &Checked).unwrap(), 5);
+        assert_eq!(school.project(18, &Checked).unwrap(), 26);
+        assert_eq!(school.project(80, &Checked).unwrap(), 5934);
+        assert_eq!(school.project(256, &Wide).unwrap(), 26984457539);
+        assert_eq!(
+            school.project(256, &Arbitrary).unwrap(),
+            BigUint::from(26984457539u64)
+        );
+    }
+
+    #[test]
+    fn test_project_matches_simulation() {
+        let fish = input_generator("3,4,3,1,2").unwrap();
+        let mut school = LanternfishSchool::new(&fish).unwrap();
+        for day in 1..=200 {
+            school.simulate_day().unwrap();
+            assert_eq!(
+                LanternfishSchool::new(&fish)
+                    .unwrap()
+                    .project(day, &Checked)
+                    .unwrap(),
+                school.count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_project_large_horizons() {
+        let school = LanternfishSchool::new(&input_generator("3,4,3,1,2").unwrap()).unwrap();
+        let modulus = NonZeroU64::new(1_000_000_007).unwrap();
+
+        assert!(matches!(
+            school.project(10_000, &Checked),
+            Err(Error::Overflow)
+        ));
+        assert!(matches!(
+            school.project(10_000, &Wide),
+            Err(Error::Overflow)
+        ));
+        assert_eq!(
+            school.project(10_000, &Arbitrary).unwrap() % modulus.get(),
+            BigUint::from(school.project(10_000, &Modulo(modulus)).unwrap())
+        );
+        assert!(school.project(1_000_000_000_000, &Modulo(modulus)).is_ok());
+    }
+
+    #[test]
+    fn test_custom_lifecycle() {
+        let spec = LifecycleSpec {
+            spawn_interval: 2,
+            maturation_delay: 0,
+            offspring: 1,
+            mortality_age: None,
+        };
+        let mut school = LanternfishSchool::with_spec(&[Lanternfish(0)], spec).unwrap();
+        let counts: Vec<usize> = (0..6)
+            .map(|_day| {
+                school.simulate_day().unwrap();
+                school.count()
+            })
+            .collect();
+        assert_eq!(counts, vec![2, 2, 4, 4, 8, 8]);
+        assert!(matches!(
+            LanternfishSchool::with_spec(&[Lanternfish(2)], spec),
+            Err(Error::TimerOutOfRange(2))
+        ));
+
+        let triplets = LifecycleSpec {
+            offspring: 3,
+            ..spec
+        };
+        let school = LanternfishSchool::with_spec(&[Lanternfish(0)], triplets).unwrap();
+        assert_eq!(school.project(1, &Checked).unwrap(), 4);
+        assert_eq!(school.project(3, &Checked).unwrap(), 16);
+    }
+
+    #[test]
+    fn test_invalid_lifecycle() {
+        assert_eq!(
+            LifecycleSpec::new(7, 2, 1, None).unwrap(),
+            LifecycleSpec::LANTERNFISH
+        );
+        assert!(matches!(
+            LifecycleSpec::new(0, 2, 1, None),
+            Err(Error::ZeroSpawnInterval)
+        ));
+        assert!(matches!(
+            LifecycleSpec::new(7, 2, 1, Some(0)),
+            Err(Error::ZeroMortalityAge)
+        ));
+        assert!(matches!(
+            LifecycleSpec::new(u32::MAX, u32::MAX, 1, None),
+            Err(Error::TooManyStates { .. })
+        ));
+        assert!(matches!(
+            LifecycleSpec::new(7, 2, 1, Some(u32::MAX)),
+            Err(Error::TooManyStates { .. })
+        ));
+
+        // Fields set directly are checked before anything is sized by them.
+        let spec = LifecycleSpec {
+            spawn_interval: 0,
+            ..LifecycleSpec::LANTERNFISH
+        };
+        assert!(matches!(
+            parse_timers("3,4", &spec),
+            Err(Error::ZeroSpawnInterval)
+        ));
+        let spec = LifecycleSpec {
+            mortality_age: Some(1 << 20),
+            ..LifecycleSpec::LANTERNFISH
+        };
+        assert!(matches!(
+            LanternfishSchool::with_spec(&[Lanternfish(3)], spec),
+            Err(Error::TooManyStates { states: 1_048_576 })
+        ));
+    }
+
+    #[test]
+    fn test_mortality() {
+        let spec = LifecycleSpec {
+            mortality_age: Some(9),
+            ..LifecycleSpec::LANTERNFISH
+        };
+        let fish = input_generator("3,4,3,1,2").unwrap();
+
+        // Every fish spawns exactly once on its last day, replacing itself.
+        let school = LanternfishSchool::with_spec(&fish, spec).unwrap();
+        assert_eq!(school.project(1000, &Checked).unwrap(), 5);
+
+        let short_lived = LifecycleSpec {
+            mortality_age: Some(8),
+            ..spec
+        };
+        let mut school = LanternfishSchool::with_spec(&fish, short_lived).unwrap();
+        for _day in 0..8 {
+            school.simulate_day().unwrap();
+        }
+        assert_eq!(school.count(), 0);
+    }
+
+    #[test]
+    fn test_series() {
+        let school = LanternfishSchool::new(&input_generator("3,4,3,1,2").unwrap()).unwrap();
+        assert_eq!(school.buckets(), vec![0, 1, 1, 2, 1, 0, 0, 0, 0]);
+
+        let series = school.series(18).unwrap();
+        assert_eq!(series.0.len(), 19);
+        assert_eq!(series.0[1].buckets, vec![1, 1, 2, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(series.0[18].total, 26);
+
+        let mut csv = Vec::new();
+        school.series(1).unwrap().write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "day,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8\n\
+             0,5,0,1,1,2,1,0,0,0,0\n\
+             1,5,1,1,2,1,0,0,0,0,0\n"
+        );
+
+        assert_eq!(school.first_day_exceeding(25, 100).unwrap(), Some(18));
+        assert_eq!(school.first_day_exceeding(5, 100).unwrap(), Some(2));
+        assert_eq!(
+            school
+                .first_day_exceeding(u128::from(u64::MAX), 10)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_growth_rate() {
+        // x^9 = x^2 + 1, from a newborn at 9 days and its parent at 7.
+        let rate = LifecycleSpec::LANTERNFISH.growth_rate();
+        assert!((rate.powi(9) - rate.powi(2) - 1.0).abs() < 1e-9);
+        assert!((rate - 1.0910).abs() < 1e-4);
+
+        let replacement = LifecycleSpec {
+            mortality_age: Some(9),
+            ..LifecycleSpec::LANTERNFISH
+        };
+        assert!((replacement.growth_rate() - 1.0).abs() < 1e-9);
+
+        let doubling = LifecycleSpec {
+            spawn_interval: 1,
+            maturation_delay: 0,
+            offspring: 1,
+            mortality_age: None,
+        };
+        assert!((doubling.growth_rate() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_input_generator() {
+        let fish = input_generator("3,4, 3\n1 ,2\n\n").unwrap();
+        assert_eq!(
+            fish.iter().map(|fish| fish.0).collect::<Vec<u8>>(),
+            vec![3, 4, 3, 1, 2]
+        );
+        assert!(input_generator("").unwrap().is_empty());
+
+        assert!(matches!(
+            input_generator("3,4\n3,x1"),
+            Err(Error::BadToken { token, line: 2, column: 3 }) if token == "x1"
+        ));
+        assert!(matches!(
+            input_generator("3,-4"),
+            Err(Error::BadToken {
+                line: 1,
+                column: 3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            input_generator("3,,4"),
+            Err(Error::BadToken { token, line: 1, column: 3 }) if token.is_empty()
+        ));
+        assert!(matches!(
+            input_generator("3,4\n1, ,2"),
+            Err(Error::BadToken { token, line: 2, column: 3 }) if token.is_empty()
+        ));
+        assert!(matches!(
+            input_generator(",3"),
+            Err(Error::BadToken { token, line: 1, column: 1 }) if token.is_empty()
+        ));
+        assert!(matches!(
+            input_generator("3,4,"),
+            Err(Error::BadToken { token, line: 1, column: 5 }) if token.is_empty()
+        ));
+        assert!(matches!(
+            input_generator("3,9"),
+            Err(Error::TimerOutOfRangeAt {
+                timer: 9,
+                line: 1,
+                column: 3
+            })
+        ));
+        assert!(matches!(
+            input_generator("3\n 300"),
+            Err(Error::TimerOutOfRangeAt {
+                timer: 300,
+                line: 2,
+                column: 2
+            })
+        ));
+
+        // The range depends on the species.
+        let slow = LifecycleSpec {
+            spawn_interval: 10,
+            ..LifecycleSpec::LANTERNFISH
+        };
+        let fish = parse_timers("3,9", &slow).unwrap();
+        assert!(LanternfishSchool::with_spec(&fish, slow).is_ok());
+        assert!(matches!(
+            parse_timers("3,12", &slow),
+            Err(Error::TimerOutOfRangeAt { timer: 12, .. })
+        ));
     }
-    school.count()
 }
@@ -1,65 +1,1131 @@
-use std::cmp::Ordering;
-
 use aoc_runner_derive::{aoc, aoc_generator};
-use itertools::{Itertools, MinMaxResult};
+use std::{
+    cmp::Ordering,
+    io::{self, Write},
+    num::ParseIntError,
+};
+
+type Number = u32;
+
+/// Fuel is totalled in a type wide enough that only extreme inputs overflow,
+/// and those are reported as errors rather than wrapping.
+pub type Fuel = u128;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("No crabs to align")]
+    NoCrabs,
+    #[error("Crab positions and weights are too large to total")]
+    PositionOverflow,
+    #[error("Fuel cost overflowed at position {position}")]
+    Overflow { position: i64 },
+    #[error("Fuel cost overflowed at ({x}, {y})")]
+    GridOverflow { x: i64, y: i64 },
+    #[error("Bad crab position {token:?}")]
+    BadPosition {
+        token: String,
+        source: ParseIntError,
+    },
+}
+
+/// A total cost, or `None` if it overflowed, so that searches can rank it
+/// above every finite cost instead of giving up.
+fn finite(cost: Result<Fuel, Error>) -> Result<Option<Fuel>, Error> {
+    match cost {
+        Ok(cost) => Ok(Some(cost)),
+        Err(Error::Overflow { .. } | Error::GridOverflow { .. }) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Crab positions in sorted order, with prefix sums so the total cost of any
+/// alignment can be found without visiting every crab.
+#[derive(Clone, Debug)]
+pub struct Crabs {
+    positions: Vec<i64>,
+    weights: Vec<Number>,
+    /// Total weight of the first `n` crabs.
+    prefix_weight: Vec<i128>,
+    /// Total of weight times position of the first `n` crabs.
+    prefix_moment: Vec<i128>,
+    /// Total of weight times squared position of the first `n` crabs.
+    prefix_square: Vec<i128>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: i64,
+    pub cost: Fuel,
+}
+
+impl Crabs {
+    pub fn new(positions: &[Number]) -> Result<Self, Error> {
+        Self::weighted(positions.iter().map(|&position| (position, 1)))
+    }
+
+    /// Crabs where moving a crab costs its weight times as much fuel.
+    pub fn weighted(crabs: impl IntoIterator<Item = (Number, Number)>) -> Result<Self, Error> {
+        let mut crabs: Vec<(i64, Number)> = crabs
+            .into_iter()
+            .map(|(position, weight)| (i64::from(position), weight))
+            .collect();
+        crabs.sort_unstable();
+
+        let mut prefix_weight: Vec<i128> = vec![0];
+        let mut prefix_moment: Vec<i128> = vec![0];
+        let mut prefix_square: Vec<i128> = vec![0];
+        for &(position, weight) in &crabs {
+            let (position, weight) = (i128::from(position), i128::from(weight));
+            let moment = weight.checked_mul(position);
+            let sums = (
+                prefix_weight.last().unwrap().checked_add(weight),
+                moment.and_then(|moment| prefix_moment.last().unwrap().checked_add(moment)),
+                moment
+                    .and_then(|moment| moment.checked_mul(position))
+                    .and_then(|square| prefix_square.last().unwrap().checked_add(square)),
+            );
+            match sums {
+                (Some(weight), Some(moment), Some(square)) => {
+                    prefix_weight.push(weight);
+                    prefix_moment.push(moment);
+                    prefix_square.push(square);
+                }
+                _ => return Err(Error::PositionOverflow),
+            }
+        }
+
+        let (positions, weights) = crabs.into_iter().unzip();
+        Ok(Self {
+            positions,
+            weights,
+            prefix_weight,
+            prefix_moment,
+            prefix_square,
+        })
+    }
+
+    fn total_weight(&self) -> i128 {
+        *self.prefix_weight.last().unwrap()
+    }
+
+    fn total_moment(&self) -> i128 {
+        *self.prefix_moment.last().unwrap()
+    }
+
+    /// The outermost crabs, as long as there is any weight to align.
+    fn range(&self) -> Result<(i64, i64), Error> {
+        match (self.positions.first(), self.positions.last()) {
+            (Some(&min), Some(&max)) if self.total_weight() > 0 => Ok((min, max)),
+            _ => Err(Error::NoCrabs),
+        }
+    }
+
+    /// Each crab's position and weight, in order of position.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, Number)> + '_ {
+        self.positions
+            .iter()
+            .copied()
+            .zip(self.weights.iter().copied())
+    }
+
+    fn checked_linear_cost(&self, position: i64) -> Option<Fuel> {
+        let below = self.positions.partition_point(|&crab| crab < position);
+        let (weight, moment) = (self.prefix_weight[below], self.prefix_moment[below]);
+        let position = i128::from(position);
 
-type Number = u16;
+        let left = weight.checked_mul(position)?.checked_sub(moment)?;
+        let right = (self.total_moment() - moment)
+            .checked_sub((self.total_weight() - weight).checked_mul(position)?)?;
+        Fuel::try_from(left.checked_add(right)?).ok()
+    }
+
+    fn checked_quadratic_cost(&self, position: i64) -> Option<Fuel> {
+        let position = i128::from(position);
+        let squares = self
+            .total_weight()
+            .checked_mul(position)?
+            .checked_mul(position)?
+            .checked_sub(self.total_moment().checked_mul(2 * position)?)?
+            .checked_add(*self.prefix_square.last().unwrap())?;
+        Fuel::try_from(squares).ok()
+    }
+
+    /// Total of weight times distance from every crab to `position`.
+    pub fn linear_cost(&self, position: i64) -> Result<Fuel, Error> {
+        self.checked_linear_cost(position)
+            .ok_or(Error::Overflow { position })
+    }
+
+    /// Total of weight times squared distance from every crab to `position`.
+    pub fn quadratic_cost(&self, position: i64) -> Result<Fuel, Error> {
+        self.checked_quadratic_cost(position)
+            .ok_or(Error::Overflow { position })
+    }
+
+    /// Total of weight times the triangular number of the distance from every
+    /// crab to `position`, which is half of the squared distances plus the
+    /// plain distances.
+    pub fn triangular_cost(&self, position: i64) -> Result<Fuel, Error> {
+        self.quadratic_cost(position)?
+            .checked_add(self.linear_cost(position)?)
+            .map(|doubled| doubled / 2)
+            .ok_or(Error::Overflow { position })
+    }
+
+    fn mean(&self) -> i64 {
+        // The mean lies between the outermost crabs, so it fits a position.
+        self.total_moment().div_euclid(self.total_weight()) as i64
+    }
+
+    /// Cheapest of the given positions under `total_cost`, clamped to the
+    /// outermost crabs. Only fails to overflow if every position does.
+    fn cheapest(
+        &self,
+        from: i64,
+        to: i64,
+        total_cost: impl Fn(i64) -> Result<Fuel, Error>,
+    ) -> Result<Alignment, Error> {
+        let (min, max) = self.range()?;
+        let (from, to) = (from.max(min), to.min(max));
+        if from > to {
+            return Err(Error::NoCrabs);
+        }
+        cheapest_of(from..=to, |position| finite(total_cost(position)))?
+            .ok_or(Error::Overflow { position: from })
+    }
+
+    /// Aligns on the weighted median, which minimizes linear cost.
+    pub fn align_linear(&self) -> Result<Alignment, Error> {
+        self.range()?;
+        let half = (self.total_weight() + 1) / 2;
+        let median = self.prefix_weight[1..].partition_point(|&weight| weight < half);
+        let position = self.positions[median];
+        Ok(Alignment {
+            position,
+            cost: self.linear_cost(position)?,
+        })
+    }
+
+    /// Aligns within half a step of the weighted mean, where the triangular
+    /// cost is minimized.
+    pub fn align_triangular(&self) -> Result<Alignment, Error> {
+        self.range()?;
+        let mean = self.mean();
+        self.cheapest(mean - 1, mean + 2, |position| {
+            self.triangular_cost(position)
+        })
+    }
+
+    /// Aligns on the weighted mean, which minimizes quadratic cost.
+    pub fn align_quadratic(&self) -> Result<Alignment, Error> {
+        self.range()?;
+        let mean = self.mean();
+        self.cheapest(mean, mean + 1, |position| self.quadratic_cost(position))
+    }
+
+    /// Aligns by ternary search for any total cost that is convex in the
+    /// position, such as `linear_cost` or `triangular_cost`. Only fails to
+    /// overflow if every position does.
+    pub fn align_convex(
+        &self,
+        total_cost: impl Fn(i64) -> Result<Fuel, Error>,
+    ) -> Result<Alignment, Error> {
+        let (min, max) = self.range()?;
+        ternary_search(min, max, &|position| finite(total_cost(position)))?
+            .ok_or(Error::Overflow { position: min })
+    }
+
+    /// The total cost of every position between the outermost crabs. Only
+    /// fails to overflow if every position does.
+    pub fn cost_curve<C: FuelCost + ?Sized>(&self, cost: &C) -> Result<CostCurve, Error> {
+        let (min, max) = self.range()?;
+        let costs: Vec<Option<Fuel>> = (min..=max)
+            .map(|position| finite(cost.total(self, position)))
+            .collect::<Result<_, _>>()?;
+        if costs.iter().all(Option::is_none) {
+            return Err(Error::Overflow { position: min });
+        }
+        Ok(CostCurve { start: min, costs })
+    }
+
+    /// Finds the cheapest alignment under any fuel cost. Only positions
+    /// between the outermost crabs are considered.
+    pub fn align<C: FuelCost + ?Sized>(&self, cost: &C) -> Result<Alignment, Error> {
+        let (min, max) = self.range()?;
+        cost.optimum(self).unwrap_or_else(|| {
+            if cost.is_convex() {
+                self.align_convex(|position| cost.total(self, position))
+            } else {
+                self.cheapest(min, max, |position| cost.total(self, position))
+            }
+        })
+    }
+}
+
+/// Total fuel for aligning at each position between the outermost crabs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CostCurve {
+    /// The position whose cost is `costs[0]`.
+    pub start: i64,
+    /// The total cost at each position, or `None` where it overflows.
+    pub costs: Vec<Option<Fuel>>,
+}
+
+impl CostCurve {
+    /// Each position with its total cost.
+    pub fn points(&self) -> impl Iterator<Item = (i64, Option<Fuel>)> + '_ {
+        (self.start..).zip(self.costs.iter().copied())
+    }
+
+    pub fn lowest_cost(&self) -> Fuel {
+        self.costs.iter().flatten().copied().min().unwrap_or(0)
+    }
+
+    /// Every position tied for the lowest cost.
+    pub fn optimal_positions(&self) -> Vec<i64> {
+        let lowest = Some(self.lowest_cost());
+        self.points()
+            .filter(|&(_position, cost)| cost == lowest)
+            .map(|(position, _cost)| position)
+            .collect()
+    }
+
+    /// Extra fuel, indexed by `k`, for the cheapest position that is `k`
+    /// steps from the nearest optimal position, or `None` if every such
+    /// position overflows.
+    pub fn sensitivity(&self) -> Vec<Option<Fuel>> {
+        let lowest = self.lowest_cost();
+
+        // Steps to the nearest optimal position, looking left then right.
+        let mut steps = vec![usize::MAX; self.costs.len()];
+        let mut nearest = None;
+        for (idx, &cost) in self.costs.iter().enumerate() {
+            if cost == Some(lowest) {
+                nearest = Some(idx);
+            }
+            if let Some(nearest) = nearest {
+                steps[idx] = idx - nearest;
+            }
+        }
+        nearest = None;
+        for (idx, &cost) in self.costs.iter().enumerate().rev() {
+            if cost == Some(lowest) {
+                nearest = Some(idx);
+            }
+            if let Some(nearest) = nearest {
+                steps[idx] = steps[idx].min(nearest - idx);
+            }
+        }
+
+        let mut sensitivity: Vec<Option<Fuel>> =
+            vec![None; steps.iter().copied().max().map_or(0, |k| k + 1)];
+        for (k, cost) in steps.into_iter().zip(&self.costs) {
+            if let Some(extra) = cost.map(|cost| cost - lowest) {
+                sensitivity[k] = Some(sensitivity[k].map_or(extra, |old| old.min(extra)));
+            }
+        }
+        sensitivity
+    }
+
+    /// Writes one row per position, with its cost and the extra fuel over
+    /// the optimum, leaving both empty where the cost overflows.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let lowest = self.lowest_cost();
+        writeln!(writer, "position,cost,extra")?;
+        for (position, cost) in self.points() {
+            match cost {
+                Some(cost) => writeln!(writer, "{},{},{}", position, cost, cost - lowest)?,
+                None => writeln!(writer, "{position},,")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the optimum, the whole curve and the sensitivity as JSON, with
+    /// `null` for costs that overflow.
+    pub fn write_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let join = |values: Vec<String>| values.join(",");
+        let number = |value: Option<Fuel>| value.map_or("null".to_string(), |n| n.to_string());
+
+        write!(
+            writer,
+            "{{\"optimum\":{{\"cost\":{},\"positions\":[{}]}},",
+            self.lowest_cost(),
+            join(
+                self.optimal_positions()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect()
+            )
+        )?;
+        write!(
+            writer,
+            "\"curve\":[{}],",
+            join(
+                self.points()
+                    .map(|(position, cost)| format!(
+                        "{{\"position\":{},\"cost\":{}}}",
+                        position,
+                        number(cost)
+                    ))
+                    .collect()
+            )
+        )?;
+        writeln!(
+            writer,
+            "\"sensitivity\":[{}]}}",
+            join(self.sensitivity().into_iter().map(number).collect())
+        )
+    }
+}
+
+/// Fuel burned by a single crab moving a given distance.
+pub trait FuelCost {
+    /// Fuel for one crab to move `distance`, or `None` if it overflows.
+    fn cost(&self, distance: u64) -> Option<Fuel>;
+
+    /// Whether `cost` is convex, which lets solvers search instead of
+    /// trying every position.
+    fn is_convex(&self) -> bool {
+        false
+    }
+
+    /// Whether every step costs the same, which splits a Manhattan distance
+    /// into independent costs along each axis.
+    fn is_linear(&self) -> bool {
+        false
+    }
+
+    /// Total fuel for every crab to move to `position`.
+    fn total(&self, crabs: &Crabs, position: i64) -> Result<Fuel, Error> {
+        crabs
+            .iter()
+            .try_fold(0, |sum: Fuel, (crab, weight)| {
+                self.cost(crab.abs_diff(position))?
+                    .checked_mul(Fuel::from(weight))?
+                    .checked_add(sum)
+            })
+            .ok_or(Error::Overflow { position })
+    }
+
+    /// The optimal alignment, for curves where it can be found directly.
+    fn optimum(&self, _crabs: &Crabs) -> Option<Result<Alignment, Error>> {
+        None
+    }
+}
+
+/// One unit of fuel per step.
+#[derive(Clone, Copy, Debug)]
+pub struct Linear;
+
+/// One more unit of fuel for each step than the step before.
+#[derive(Clone, Copy, Debug)]
+pub struct Triangular;
+
+/// Fuel equal to the square of the distance.
+#[derive(Clone, Copy, Debug)]
+pub struct Quadratic;
+
+/// Another curve, but never more than `cap` fuel per crab.
+#[derive(Clone, Copy, Debug)]
+pub struct Capped<C> {
+    pub inner: C,
+    pub cap: Fuel,
+}
+
+/// Marks another curve, such as a closure, as convex.
+#[derive(Clone, Copy, Debug)]
+pub struct Convex<C>(pub C);
+
+impl FuelCost for Linear {
+    fn cost(&self, distance: u64) -> Option<Fuel> {
+        Some(Fuel::from(distance))
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn total(&self, crabs: &Crabs, position: i64) -> Result<Fuel, Error> {
+        crabs.linear_cost(position)
+    }
+
+    fn optimum(&self, crabs: &Crabs) -> Option<Result<Alignment, Error>> {
+        Some(crabs.align_linear())
+    }
+}
+
+impl FuelCost for Triangular {
+    fn cost(&self, distance: u64) -> Option<Fuel> {
+        let distance = Fuel::from(distance);
+        Some(distance.checked_mul(distance + 1)? / 2)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn total(&self, crabs: &Crabs, position: i64) -> Result<Fuel, Error> {
+        crabs.triangular_cost(position)
+    }
+
+    fn optimum(&self, crabs: &Crabs) -> Option<Result<Alignment, Error>> {
+        Some(crabs.align_triangular())
+    }
+}
+
+impl FuelCost for Quadratic {
+    fn cost(&self, distance: u64) -> Option<Fuel> {
+        Fuel::from(distance).checked_mul(Fuel::from(distance))
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn total(&self, crabs: &Crabs, position: i64) -> Result<Fuel, Error> {
+        crabs.quadratic_cost(position)
+    }
+
+    fn optimum(&self, crabs: &Crabs) -> Option<Result<Alignment, Error>> {
+        Some(crabs.align_quadratic())
+    }
+}
+
+impl<C: FuelCost> FuelCost for Capped<C> {
+    fn cost(&self, distance: u64) -> Option<Fuel> {
+        // A curve too steep to compute is certainly above the cap.
+        Some(
+            self.inner
+                .cost(distance)
+                .map_or(self.cap, |cost| cost.min(self.cap)),
+        )
+    }
+}
+
+impl<C: FuelCost> FuelCost for Convex<C> {
+    fn cost(&self, distance: u64) -> Option<Fuel> {
+        self.0.cost(distance)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn total(&self, crabs: &Crabs, position: i64) -> Result<Fuel, Error> {
+        self.0.total(crabs, position)
+    }
+}
+
+impl<F: Fn(u64) -> Fuel> FuelCost for F {
+    fn cost(&self, distance: u64) -> Option<Fuel> {
+        Some(self(distance))
+    }
+}
+
+/// A crab on a grid, burning `weight` times as much fuel as a plain crab.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridCrab {
+    pub x: Number,
+    pub y: Number,
+    pub weight: Number,
+}
+
+/// How far a crab on a grid has to move to reach a point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// Horizontal and vertical steps only.
+    Manhattan,
+    /// Diagonal steps cost the same as straight ones.
+    Chebyshev,
+}
+
+impl Metric {
+    fn distance(&self, dx: u64, dy: u64) -> u64 {
+        match self {
+            Metric::Manhattan => dx + dy,
+            Metric::Chebyshev => dx.max(dy),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridAlignment {
+    pub x: i64,
+    pub y: i64,
+    pub cost: Fuel,
+}
+
+#[derive(Clone, Debug)]
+pub struct GridCrabs(Vec<GridCrab>);
+
+impl GridCrabs {
+    pub fn new(crabs: impl IntoIterator<Item = GridCrab>) -> Self {
+        Self(crabs.into_iter().collect())
+    }
+
+    /// The corners of the box around every crab with any weight.
+    #[allow(clippy::type_complexity)]
+    fn bounds(&self) -> Result<((i64, i64), (i64, i64)), Error> {
+        let mut crabs = self.0.iter().filter(|crab| crab.weight > 0);
+        let first = crabs.next().ok_or(Error::NoCrabs)?;
+        let start = (
+            (i64::from(first.x), i64::from(first.y)),
+            (i64::from(first.x), i64::from(first.y)),
+        );
+        Ok(crabs.fold(start, |((min_x, min_y), (max_x, max_y)), crab| {
+            let (x, y) = (i64::from(crab.x), i64::from(crab.y));
+            ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)))
+        }))
+    }
+
+    /// Total fuel for every crab to move to `(x, y)`.
+    pub fn total<C: FuelCost + ?Sized>(
+        &self,
+        metric: Metric,
+        cost: &C,
+        x: i64,
+        y: i64,
+    ) -> Result<Fuel, Error> {
+        self.0
+            .iter()
+            .try_fold(0, |sum: Fuel, crab| {
+                let distance =
+                    metric.distance(i64::from(crab.x).abs_diff(x), i64::from(crab.y).abs_diff(y));
+                cost.cost(distance)?
+                    .checked_mul(Fuel::from(crab.weight))?
+                    .checked_add(sum)
+            })
+            .ok_or(Error::GridOverflow { x, y })
+    }
+
+    /// Finds the cheapest meeting point under any metric and fuel cost. Only
+    /// points inside the box around the crabs are considered.
+    pub fn align<C: FuelCost + ?Sized>(
+        &self,
+        metric: Metric,
+        cost: &C,
+    ) -> Result<GridAlignment, Error> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds()?;
+
+        if metric == Metric::Manhattan && cost.is_linear() {
+            return self.align_axes();
+        }
+
+        // The cheapest `y` for a given `x`. Along a column both metrics are
+        // convex in `y` and never shrink away from the crab, so a convex fuel
+        // cost stays convex and can be searched; otherwise every row is tried.
+        // The cheapest cost per column has no such guarantee, so every column
+        // is tried.
+        let mut best: Option<GridAlignment> = None;
+        for x in min_x..=max_x {
+            let total = |y: i64| finite(self.total(metric, cost, x, y));
+            let row = if cost.is_convex() {
+                ternary_search(min_y, max_y, &total)?
+            } else {
+                cheapest_of(min_y..=max_y, total)?
+            };
+            if let Some(row) = row {
+                if best.map_or(true, |best| row.cost < best.cost) {
+                    best = Some(GridAlignment {
+                        x,
+                        y: row.position,
+                        cost: row.cost,
+                    });
+                }
+            }
+        }
+        best.ok_or(Error::GridOverflow { x: min_x, y: min_y })
+    }
+
+    /// Aligns under linear cost and the Manhattan metric, where the total is
+    /// the linear cost along `x` plus that along `y`, by aligning each axis
+    /// on its own.
+    fn align_axes(&self) -> Result<GridAlignment, Error> {
+        let axis = |coordinate: fn(&GridCrab) -> Number| {
+            Crabs::weighted(
+                self.0
+                    .iter()
+                    .filter(|crab| crab.weight > 0)
+                    .map(|crab| (coordinate(crab), crab.weight)),
+            )?
+            .align_linear()
+        };
+        let (x, y) = (axis(|crab| crab.x)?, axis(|crab| crab.y)?);
+        Ok(GridAlignment {
+            x: x.position,
+            y: y.position,
+            cost: x.cost.checked_add(y.cost).ok_or(Error::GridOverflow {
+                x: x.position,
+                y: y.position,
+            })?,
+        })
+    }
+}
+
+/// Orders a cost that may have overflowed above every finite cost.
+fn rank(cost: Option<Fuel>) -> (bool, Fuel) {
+    (cost.is_none(), cost.unwrap_or(0))
+}
+
+/// The first of `positions` with the lowest cost, skipping any that
+/// overflow, or `None` if they all do.
+fn cheapest_of(
+    positions: impl IntoIterator<Item = i64>,
+    cost: impl Fn(i64) -> Result<Option<Fuel>, Error>,
+) -> Result<Option<Alignment>, Error> {
+    let mut best: Option<Alignment> = None;
+    for position in positions {
+        if let Some(cost) = cost(position)? {
+            if best.map_or(true, |best| cost < best.cost) {
+                best = Some(Alignment { position, cost });
+            }
+        }
+    }
+    Ok(best)
+}
+
+/// Finds the lowest position minimizing a convex function over `lo..=hi`,
+/// where `None` stands for an overflowing cost, or `None` if every position
+/// overflows. Overflow only happens above some level, so the positions that
+/// don't overflow are one stretch, and ranking overflow above any finite cost
+/// keeps the function convex.
+fn ternary_search(
+    mut lo: i64,
+    mut hi: i64,
+    cost: &impl Fn(i64) -> Result<Option<Fuel>, Error>,
+) -> Result<Option<Alignment>, Error> {
+    while hi - lo > 2 {
+        let third = (hi - lo) / 3;
+        let (left, right) = (lo + third, hi - third);
+        let (left_cost, right_cost) = (cost(left)?, cost(right)?);
+        if left_cost.is_none() && right_cost.is_none() {
+            // The finite stretch, if any, lies wholly within one of the
+            // thirds the probes split the range into.
+            for (from, to) in [(left + 1, right - 1), (lo, left - 1), (right + 1, hi)] {
+                if let Some(best) = ternary_search(from, to, cost)? {
+                    return Ok(Some(best));
+                }
+            }
+            return Ok(None);
+        }
+        match rank(left_cost).cmp(&rank(right_cost)) {
+            Ordering::Less => hi = right - 1,
+            Ordering::Greater => lo = left + 1,
+            Ordering::Equal => {
+                lo = left;
+                hi = right;
+            }
+        }
+    }
+    cheapest_of(lo..=hi, cost)
+}
 
 #[aoc_generator(day7)]
-fn input_parser(input: &str) -> Vec<Number> {
+pub(crate) fn input_parser(input: &str) -> Result<Vec<Number>, Error> {
     input
         .split(',')
-        .map(str::parse::<Number>)
-        .collect::<Result<Vec<_>, _>>()
-        .unwrap()
+        .map(|token| {
+            str::parse::<Number>(token).map_err(|source| Error::BadPosition {
+                token: token.to_string(),
+                source,
+            })
+        })
+        .collect()
 }
 
 #[aoc(day7, part1)]
-fn part1(input: &[Number]) -> u32 {
-    if let MinMaxResult::MinMax(min, max) = input.iter().minmax() {
-        let mut lowest_cost: u32 = u32::MAX;
-        let mut cost: u32;
-        for n in *min..=*max {
-            cost = input
-                .iter()
-                .map(|this| u32::try_from((n as i32 - *this as i32).abs()).unwrap())
-                .sum();
-            if cost.cmp(&lowest_cost) == Ordering::Less {
-                lowest_cost = cost;
-            }
-        }
-        lowest_cost
-    } else {
-        unreachable!();
-    }
+pub(crate) fn part1(input: &[Number]) -> Result<Fuel, Error> {
+    Crabs::new(input)?
+        .align(&Linear)
+        .map(|alignment| alignment.cost)
 }
 
 #[aoc(day7, part2)]
-fn part2(input: &[Number]) -> u32 {
-    if let MinMaxResult::MinMax(min, max) = input.iter().minmax() {
-        let mut lowest_cost: u32 = u32::MAX;
-        let mut cost: u32;
+pub(crate) fn part2(input: &[Number]) -> Result<Fuel, Error> {
+    Crabs::new(input)?
+        .align(&Triangular)
+        .map(|alignment| alignment.cost)
+}
 
-        let cost_for_distance: Vec<usize> = (*min..=*max)
-            .into_iter()
-            .scan(0usize, |state, n| {
-                *state += n as usize;
-                Some(*state)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tries every position between the outermost crabs.
+    fn brute_force(crabs: &[(Number, Number)], cost: impl Fn(u64) -> Fuel) -> Fuel {
+        let min = crabs.iter().map(|crab| crab.0).min().unwrap();
+        let max = crabs.iter().map(|crab| crab.0).max().unwrap();
+        (min..=max)
+            .map(|n| {
+                crabs
+                    .iter()
+                    .map(|&(this, weight)| Fuel::from(weight) * cost(u64::from(n.abs_diff(this))))
+                    .sum()
             })
-            .collect();
+            .min()
+            .unwrap()
+    }
+
+    fn triangular(distance: u64) -> Fuel {
+        Fuel::from(distance * (distance + 1) / 2)
+    }
+
+    #[test]
+    fn test_example() {
+        let input = input_parser("16,1,2,0,4,2,7,1,2,14").unwrap();
+        let crabs = Crabs::new(&input).unwrap();
 
-        for n in *min..=*max {
-            cost = input
+        assert_eq!(
+            crabs.align_linear().unwrap(),
+            Alignment {
+                position: 2,
+                cost: 37
+            }
+        );
+        assert_eq!(
+            crabs.align_triangular().unwrap(),
+            Alignment {
+                position: 5,
+                cost: 168
+            }
+        );
+        assert_eq!(crabs.linear_cost(10).unwrap(), 71);
+        assert_eq!(crabs.triangular_cost(2).unwrap(), 206);
+        assert!(matches!(part1(&[]), Err(Error::NoCrabs)));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        // Small linear congruential generator, for repeatable pseudo-random crabs.
+        let mut seed = 7u32;
+        let mut next = |modulus: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % modulus
+        };
+
+        for _round in 0..50 {
+            let count = 1 + next(30) as usize;
+            let crabs: Vec<(Number, Number)> =
+                (0..count).map(|_crab| (next(200), 1 + next(5))).collect();
+            let unweighted: Vec<(Number, Number)> = crabs
                 .iter()
-                .map(|this| u32::try_from((n as i32 - *this as i32).abs()).unwrap())
-                .map(|n| cost_for_distance[n as usize] as u32)
-                .sum();
-            if cost.cmp(&lowest_cost) == Ordering::Less {
-                lowest_cost = cost;
+                .map(|&(position, _weight)| (position, 1))
+                .collect();
+            let positions: Vec<Number> = crabs.iter().map(|crab| crab.0).collect();
+
+            let plain = Crabs::new(&positions).unwrap();
+            assert_eq!(
+                plain.align_linear().unwrap().cost,
+                brute_force(&unweighted, Fuel::from)
+            );
+            assert_eq!(
+                plain.align_triangular().unwrap().cost,
+                brute_force(&unweighted, triangular)
+            );
+
+            let weighted = Crabs::weighted(crabs.iter().copied()).unwrap();
+            assert_eq!(
+                weighted.align_linear().unwrap().cost,
+                brute_force(&crabs, Fuel::from)
+            );
+            assert_eq!(
+                weighted.align_triangular().unwrap().cost,
+                brute_force(&crabs, triangular)
+            );
+            assert_eq!(
+                weighted
+                    .align_convex(|position| weighted.triangular_cost(position))
+                    .unwrap()
+                    .cost,
+                brute_force(&crabs, triangular)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuel_costs() {
+        let crabs: Vec<(Number, Number)> =
+            vec![(16, 1), (1, 2), (2, 1), (0, 3), (4, 1), (7, 2), (14, 1)];
+        let weighted = Crabs::weighted(crabs.iter().copied()).unwrap();
+
+        let check = |cost: &dyn FuelCost| {
+            assert_eq!(
+                weighted.align(cost).unwrap().cost,
+                brute_force(&crabs, |d| cost.cost(d).unwrap())
+            );
+        };
+
+        check(&Linear);
+        check(&Triangular);
+        check(&Quadratic);
+        check(&Capped {
+            inner: Quadratic,
+            cap: 20,
+        });
+        check(&|d: u64| Fuel::from(d * d * d));
+        check(&Convex(|d: u64| Fuel::from(d * d * d)));
+        check(&|d: u64| Fuel::from(d + 4 * (d % 3)));
+
+        assert_eq!(
+            Crabs::new(&[0, 10, 11])
+                .unwrap()
+                .align(&Capped {
+                    inner: Linear,
+                    cap: 3
+                })
+                .unwrap(),
+            Alignment {
+                position: 10,
+                cost: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_wide_inputs() {
+        let crabs = Crabs::weighted([(0, Number::MAX), (4_000_000_000, Number::MAX)]).unwrap();
+        assert_eq!(
+            crabs.align(&Quadratic).unwrap(),
+            Alignment {
+                position: 2_000_000_000,
+                cost: 2 * Fuel::from(Number::MAX) * 4_000_000_000_000_000_000
+            }
+        );
+
+        let steep = |d: u64| if d == 0 { 0 } else { Fuel::MAX / 2 + 1 };
+        assert!(matches!(
+            Crabs::new(&[0, 1, 2]).unwrap().cost_curve(&steep),
+            Err(Error::Overflow { .. })
+        ));
+        assert!(matches!(
+            Crabs::new(&[0, 1, 2]).unwrap().align(&steep),
+            Err(Error::Overflow { .. })
+        ));
+        assert!(matches!(
+            Crabs::weighted([(3, 0)]).unwrap().align(&Linear),
+            Err(Error::NoCrabs)
+        ));
+    }
+
+    /// Linear, but overflowing for distances over five.
+    struct Short;
+
+    impl FuelCost for Short {
+        fn cost(&self, distance: u64) -> Option<Fuel> {
+            (distance <= 5).then_some(Fuel::from(distance))
+        }
+    }
+
+    #[test]
+    fn test_overflowing_candidates() {
+        let crabs = Crabs::new(&[0, 10, 10]).unwrap();
+        let optimum = Alignment {
+            position: 5,
+            cost: 15,
+        };
+        assert_eq!(crabs.align(&Short).unwrap(), optimum);
+        assert_eq!(crabs.align(&Convex(Short)).unwrap(), optimum);
+
+        let curve = crabs.cost_curve(&Short).unwrap();
+        assert_eq!(curve.optimal_positions(), vec![5]);
+        assert_eq!(curve.costs[4], None);
+        assert_eq!(curve.sensitivity(), [Some(0), None, None, None, None, None]);
+        let mut csv = Vec::new();
+        curve.write_csv(&mut csv).unwrap();
+        assert!(String::from_utf8(csv).unwrap().contains("\n4,,\n5,15,0\n"));
+
+        // Only the far left end is representable, outside the middle third
+        // the first probes narrow towards.
+        let steep = |d: u64| Fuel::from(d * d) * (Fuel::MAX / 500);
+        let crabs = Crabs::weighted([(0, 1000), (10, 1)]).unwrap();
+        assert_eq!(crabs.align(&Convex(steep)).unwrap().position, 0);
+    }
+
+    #[test]
+    fn test_grid_alignment() {
+        let mut seed = 11u32;
+        let mut next = |modulus: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % modulus
+        };
+
+        for _round in 0..200 {
+            let crabs = GridCrabs::new(
+                (0..1 + next(12))
+                    .map(|_crab| GridCrab {
+                        x: next(40),
+                        y: next(40),
+                        weight: 1 + next(4),
+                    })
+                    .collect::<Vec<_>>(),
+            );
+
+            let costs: [&dyn FuelCost; 4] = [&Linear, &Triangular, &Quadratic, &|d: u64| {
+                Fuel::from(d.min(6))
+            }];
+            for metric in [Metric::Manhattan, Metric::Chebyshev] {
+                for cost in costs {
+                    let ((min_x, min_y), (max_x, max_y)) = crabs.bounds().unwrap();
+                    let brute_force = (min_x..=max_x)
+                        .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+                        .map(|(x, y)| crabs.total(metric, cost, x, y).unwrap())
+                        .min()
+                        .unwrap();
+                    assert_eq!(crabs.align(metric, cost).unwrap().cost, brute_force);
+                }
             }
         }
-        lowest_cost
-    } else {
-        unreachable!();
+    }
+
+    #[test]
+    fn test_grid_plateaus() {
+        let mut seed = 7u32;
+        let mut next = |modulus: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % modulus
+        };
+
+        // Costs that are flat near the crabs, and crabs stacked on few rows
+        // and columns, leave long runs of equally cheap points.
+        let free = Convex(|d: u64| Fuel::from(d.saturating_sub(3)));
+        let shallow = Convex(|d: u64| Fuel::from(d.saturating_sub(2).pow(2)));
+        let linear = Convex(|d: u64| Fuel::from(d));
+        let costs: [&dyn FuelCost; 5] = [&Linear, &linear, &free, &shallow, &Triangular];
+        for _round in 0..300 {
+            let mut crabs: Vec<GridCrab> = (0..1 + next(8))
+                .map(|_crab| GridCrab {
+                    x: 5 * next(4),
+                    y: 5 * next(4),
+                    weight: next(3),
+                })
+                .collect();
+            crabs.push(GridCrab {
+                x: next(16),
+                y: next(16),
+                weight: 1,
+            });
+            let crabs = GridCrabs::new(crabs);
+            let ((min_x, min_y), (max_x, max_y)) = crabs.bounds().unwrap();
+            for metric in [Metric::Manhattan, Metric::Chebyshev] {
+                for cost in costs {
+                    let brute_force = (min_x..=max_x)
+                        .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+                        .map(|(x, y)| crabs.total(metric, cost, x, y).unwrap())
+                        .min()
+                        .unwrap();
+                    let best = crabs.align(metric, cost).unwrap();
+                    assert_eq!(best.cost, brute_force);
+                    assert_eq!(
+                        crabs.total(metric, cost, best.x, best.y).unwrap(),
+                        best.cost
+                    );
+                    assert!((min_x..=max_x).contains(&best.x));
+                    assert!((min_y..=max_y).contains(&best.y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_example() {
+        let crabs = GridCrabs::new([
+            GridCrab {
+                x: 0,
+                y: 0,
+                weight: 1,
+            },
+            GridCrab {
+                x: 4,
+                y: 2,
+                weight: 1,
+            },
+            GridCrab {
+                x: 2,
+                y: 8,
+                weight: 3,
+            },
+        ]);
+
+        assert_eq!(
+            crabs.align(Metric::Manhattan, &Linear).unwrap(),
+            GridAlignment {
+                x: 2,
+                y: 8,
+                cost: 18
+            }
+        );
+        assert_eq!(
+            crabs.align(Metric::Chebyshev, &Linear).unwrap().cost,
+            (0..=4)
+                .flat_map(|x| (0..=8).map(move |y| (x, y)))
+                .map(|(x, y)| crabs.total(Metric::Chebyshev, &Linear, x, y).unwrap())
+                .min()
+                .unwrap()
+        );
+        assert!(matches!(
+            GridCrabs::new([]).align(Metric::Chebyshev, &Triangular),
+            Err(Error::NoCrabs)
+        ));
+        assert!(matches!(
+            crabs.total(Metric::Manhattan, &Short, 3, 1),
+            Err(Error::GridOverflow { x: 3, y: 1 })
+        ));
+        // Only points within five steps of every crab are representable.
+        assert_eq!(
+            crabs.align(Metric::Chebyshev, &Convex(Short)).unwrap().cost,
+            (0..=4)
+                .flat_map(|x| (0..=8).map(move |y| (x, y)))
+                .filter_map(|(x, y)| crabs.total(Metric::Chebyshev, &Short, x, y).ok())
+                .min()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cost_curve() {
+        let crabs = Crabs::new(&input_parser("16,1,2,0,4,2,7,1,2,14").unwrap()).unwrap();
+        let curve = crabs.cost_curve(&Linear).unwrap();
+
+        assert_eq!(curve.start, 0);
+        assert_eq!(curve.costs.len(), 17);
+        assert_eq!(&curve.costs[..4], &[Some(49), Some(41), Some(37), Some(39)]);
+        assert_eq!(curve.costs[10], Some(71));
+        assert_eq!(curve.optimal_positions(), vec![2]);
+        assert_eq!(&curve.sensitivity()[..3], &[Some(0), Some(2), Some(4)]);
+        assert_eq!(curve.sensitivity().len(), 15);
+
+        let curve = crabs.cost_curve(&Triangular).unwrap();
+        assert_eq!(curve.lowest_cost(), 168);
+        assert_eq!(curve.optimal_positions(), vec![5]);
+
+        let tied = Crabs::new(&[0, 3]).unwrap().cost_curve(&Linear).unwrap();
+        assert_eq!(tied.optimal_positions(), vec![0, 1, 2, 3]);
+        assert_eq!(tied.sensitivity(), vec![Some(0)]);
+
+        let mut csv = Vec::new();
+        tied.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "position,cost,extra\n0,3,0\n1,3,0\n2,3,0\n3,3,0\n"
+        );
+
+        let mut json = Vec::new();
+        Crabs::new(&[0, 2])
+            .unwrap()
+            .cost_curve(&Triangular)
+            .unwrap()
+            .write_json(&mut json)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"optimum\":{\"cost\":2,\"positions\":[1]},\
+             \"curve\":[{\"position\":0,\"cost\":3},{\"position\":1,\"cost\":2},{\"position\":2,\"cost\":3}],\
+             \"sensitivity\":[0,1]}\n"
+        );
     }
 }
@@ -1,13 +1,16 @@
+use crate::wiring::{self, Alphabet, SEVEN_SEGMENT_DIGITS};
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
-    character::complete::{line_ending, one_of},
-    combinator::map,
-    multi::{many1, separated_list1},
-    sequence::separated_pair,
+    character::complete::one_of,
+    combinator::{all_consuming, cut, map, not},
+    error::{context, VerboseError, VerboseErrorKind},
+    multi::{fold_many1, separated_list1},
+    sequence::{pair, separated_pair},
     IResult,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 #[cfg(test)]
 type Number = u8;
@@ -15,8 +18,8 @@ type Number = u8;
 #[cfg(test)]
 const SIGNAL_COUNT: [Number; 10] = [6, 2, 5, 5, 4, 5, 6, 3, 7, 6];
 
-#[derive(Debug, PartialEq)]
-enum Segment {
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Segment {
     A,
     B,
     C,
@@ -26,9 +29,9 @@ enum Segment {
     G,
 }
 
-#[allow(unused)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum Digit {
+    Zero,
     One,
     Two,
     Three,
@@ -40,12 +43,264 @@ enum Digit {
     Nine,
 }
 
-fn infer_digit(sequence: &[Segment], known: &HashMap<u8, Digit>) -> Option<Digit> {
-    known.get(&u8::try_from(sequence.len()).unwrap()).copied()
+const SEGMENTS: [Segment; 7] = [
+    Segment::A,
+    Segment::B,
+    Segment::C,
+    Segment::D,
+    Segment::E,
+    Segment::F,
+    Segment::G,
+];
+
+/// A set of lit segments, one bit per segment with `a` as the lowest bit.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Pattern(u8);
+
+impl Pattern {
+    pub const EMPTY: Self = Self(0);
+    pub const ALL: Self = Self(0b111_1111);
+
+    /// Takes the low seven bits of a segment mask, with `a` as the lowest.
+    const fn from_mask(mask: u32) -> Self {
+        Self(mask as u8 & Self::ALL.0)
+    }
+
+    /// Builds a pattern from its letters, panicking on anything outside `a..=g`.
+    pub const fn from_letters(letters: &str) -> Self {
+        let letters = letters.as_bytes();
+        let mut mask = 0;
+        let mut i = 0;
+        while i < letters.len() {
+            assert!(matches!(letters[i], b'a'..=b'g'), "not a segment letter");
+            mask |= 1 << (letters[i] - b'a');
+            i += 1;
+        }
+        Self(mask)
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub const fn contains(self, segment: Segment) -> bool {
+        self.0 & (1 << segment as u8) != 0
+    }
+
+    /// The number of lit segments.
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn segments(self) -> impl Iterator<Item = Segment> {
+        SEGMENTS
+            .into_iter()
+            .filter(move |&segment| self.contains(segment))
+    }
+}
+
+impl From<Segment> for Pattern {
+    fn from(segment: Segment) -> Self {
+        Self(1 << segment as u8)
+    }
+}
+
+impl From<Pattern> for u32 {
+    fn from(pattern: Pattern) -> Self {
+        pattern.0.into()
+    }
+}
+
+impl FromIterator<Segment> for Pattern {
+    fn from_iter<I: IntoIterator<Item = Segment>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::EMPTY, |pattern, segment| {
+            pattern.union(segment.into())
+        })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.segments() {
+            write!(f, "{}", char::from(b'a' + segment as u8))?;
+        }
+        Ok(())
+    }
+}
+
+/// The segments lit for each digit on a correctly wired display, in the
+/// order of the wiring solver's seven-segment alphabet.
+const DIGITS: [(Digit, Pattern); 10] = [
+    (Digit::Zero, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[0])),
+    (Digit::One, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[1])),
+    (Digit::Two, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[2])),
+    (Digit::Three, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[3])),
+    (Digit::Four, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[4])),
+    (Digit::Five, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[5])),
+    (Digit::Six, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[6])),
+    (Digit::Seven, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[7])),
+    (Digit::Eight, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[8])),
+    (Digit::Nine, Pattern::from_mask(SEVEN_SEGMENT_DIGITS[9])),
+];
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Error {
+    #[error("No wiring fits the patterns on line {line}")]
+    Inconsistent { line: usize },
+    #[error("{wirings} wirings fit the patterns on line {line}")]
+    Ambiguous { line: usize, wirings: usize },
+    #[error(transparent)]
+    Wiring(#[from] wiring::Error),
+    #[error("Line {line}, column {column}: {message}")]
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("Line {line} has {count} patterns instead of ten")]
+    PatternCount { line: usize, count: usize },
+}
+
+impl Digit {
+    fn value(self) -> usize {
+        DIGITS
+            .iter()
+            .position(|(digit, _segments)| *digit == self)
+            .unwrap()
+    }
+
+    /// The digit lit by exactly this pattern on a correctly wired display.
+    fn from_pattern(pattern: Pattern) -> Option<Self> {
+        DIGITS
+            .iter()
+            .find_map(|&(digit, lit)| (lit == pattern).then_some(digit))
+    }
+}
+
+/// The real segment driven by each scrambled wire, indexed by wire.
+type Wiring = [Segment; 7];
+
+fn rewire(pattern: Pattern, wiring: &Wiring) -> Pattern {
+    pattern
+        .segments()
+        .map(|wire| wiring[wire as usize])
+        .collect()
+}
+
+/// Finds the one wiring that turns each pattern seen on a display into a
+/// digit. Errors name the display by its input `line`.
+fn solve_wiring(patterns: &[Pattern], line: usize) -> Result<Wiring, Error> {
+    let observations: Vec<u32> = patterns.iter().map(|&pattern| pattern.into()).collect();
+    let mappings = wiring::solve(&Alphabet::seven_segment(), &observations)?;
+
+    match &mappings[..] {
+        [mapping] => Ok(std::array::from_fn(|wire| SEGMENTS[mapping.segment(wire)])),
+        [] => Err(Error::Inconsistent { line }),
+        _ => Err(Error::Ambiguous {
+            line,
+            wirings: mappings.len(),
+        }),
+    }
+}
+
+/// Reads the number shown on one display, given its ten patterns and its
+/// output digits.
+fn decode((patterns, outputs): &Entry, line: usize) -> Result<usize, Error> {
+    let wiring = solve_wiring(patterns, line)?;
+    outputs.iter().try_fold(0, |number, output| {
+        Digit::from_pattern(rewire(*output, &wiring))
+            .map(|digit| number * 10 + digit.value())
+            .ok_or(Error::Inconsistent { line })
+    })
+}
+
+/// Draws a pattern as a 3×3 seven-segment glyph, one string per row, with the
+/// uncertain segments drawn as `?`.
+pub fn render(lit: Pattern, uncertain: Pattern) -> [String; 3] {
+    let draw = |segment: Segment, on: char| {
+        if uncertain.contains(segment) {
+            '?'
+        } else if lit.contains(segment) {
+            on
+        } else {
+            ' '
+        }
+    };
+    [
+        [' ', draw(Segment::A, '_'), ' '],
+        [
+            draw(Segment::B, '|'),
+            draw(Segment::D, '_'),
+            draw(Segment::C, '|'),
+        ],
+        [
+            draw(Segment::E, '|'),
+            draw(Segment::G, '_'),
+            draw(Segment::F, '|'),
+        ],
+    ]
+    .map(|row| row.iter().collect())
+}
+
+/// Draws glyphs side by side, as given by pairs of lit and uncertain segments.
+pub fn render_display(glyphs: &[(Pattern, Pattern)]) -> String {
+    let glyphs: Vec<_> = glyphs
+        .iter()
+        .map(|&(lit, uncertain)| render(lit, uncertain))
+        .collect();
+    (0..3)
+        .map(|row| glyphs.iter().map(|glyph| &glyph[row][..]).join(" "))
+        .join("\n")
+}
+
+/// Draws the outputs of a display as far as its patterns pin down the wiring:
+/// segments lit under every consistent wiring are drawn, and those lit under
+/// only some of them are marked uncertain. Errors name the display by its
+/// input `line`.
+pub fn diagnose(patterns: &[Pattern], outputs: &[Pattern], line: usize) -> Result<String, Error> {
+    let observations: Vec<u32> = patterns.iter().map(|&pattern| pattern.into()).collect();
+    let mappings = wiring::solve(&Alphabet::seven_segment(), &observations)?;
+    if mappings.is_empty() {
+        return Err(Error::Inconsistent { line });
+    }
+
+    let glyphs: Vec<_> = outputs
+        .iter()
+        .map(|&output| {
+            let (always, sometimes) = mappings.iter().fold(
+                (Pattern::ALL, Pattern::EMPTY),
+                |(always, sometimes), mapping| {
+                    let lit = output
+                        .segments()
+                        .map(|wire| SEGMENTS[mapping.segment(wire as usize)])
+                        .collect();
+                    (always.intersection(lit), sometimes.union(lit))
+                },
+            );
+            (always, sometimes.difference(always))
+        })
+        .collect();
+    Ok(render_display(&glyphs))
+}
+
+fn infer_digit(pattern: Pattern, known: &HashMap<u8, Digit>) -> Option<Digit> {
+    known.get(&u8::try_from(pattern.len()).unwrap()).copied()
 }
 
 fn segment_from_char(c: char) -> Segment {
-    match c {
+    match c.to_ascii_lowercase() {
         'a' => Segment::A,
         'b' => Segment::B,
         'c' => Segment::C,
@@ -57,36 +312,120 @@ fn segment_from_char(c: char) -> Segment {
     }
 }
 
-type Sequence = Vec<Segment>;
+/// The ten unique patterns of a display, then its output digits.
+pub type Entry = (Vec<Pattern>, Vec<Pattern>);
+
+type ParseResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+fn parse_segment(input: &str) -> ParseResult<'_, Segment> {
+    map(one_of("abcdefgABCDEFG"), segment_from_char)(input)
+}
+
+fn parse_pattern(input: &str) -> ParseResult<'_, Pattern> {
+    context(
+        "a pattern of segments a to g",
+        fold_many1(parse_segment, Pattern::default, |pattern, segment| {
+            pattern.union(segment.into())
+        }),
+    )(input)
+}
 
-fn parse_segment(input: &str) -> IResult<&str, Segment> {
-    map(one_of("abcdefg"), segment_from_char)(input)
+/// Patterns separated by single spaces. Once a space is seen, a pattern
+/// must follow unless the space begins `" | "`.
+fn parse_sequence(input: &str) -> ParseResult<'_, Vec<Pattern>> {
+    separated_list1(pair(tag(" "), not(tag("| "))), cut(parse_pattern))(input)
 }
 
-fn parse_sequence(input: &str) -> IResult<&str, Vec<Sequence>> {
-    separated_list1(tag(" "), many1(parse_segment))(input)
+fn parse_inputs_and_outputs(input: &str) -> ParseResult<'_, Entry> {
+    separated_pair(
+        parse_sequence,
+        context("\" | \" between patterns and outputs", tag(" | ")),
+        parse_sequence,
+    )(input)
 }
 
-fn parse_inputs_and_outputs(input: &str) -> IResult<&str, (Vec<Sequence>, Vec<Sequence>)> {
-    separated_pair(parse_sequence, tag(" | "), parse_sequence)(input)
+/// Turns a failed parse of one line into an error pointing at the innermost
+/// failure, described by the closest context around it.
+fn syntax_error(line: usize, text: &str, error: VerboseError<&str>) -> Error {
+    let column = error
+        .errors
+        .first()
+        .map_or(0, |(rest, _kind)| text.len() - rest.len())
+        + 1;
+    let message = error
+        .errors
+        .iter()
+        .find_map(|(_rest, kind)| match kind {
+            VerboseErrorKind::Context(context) => Some(format!("expected {context}")),
+            _ => None,
+        })
+        .unwrap_or_else(|| "unexpected trailing input".to_string());
+    Error::Syntax {
+        line,
+        column,
+        message,
+    }
 }
 
-#[allow(clippy::type_complexity)]
-fn parse_all_inputs(input: &str) -> IResult<&str, Vec<(Vec<Sequence>, Vec<Sequence>)>> {
-    separated_list1(line_ending, parse_inputs_and_outputs)(input)
+fn parse_entry(line: usize, text: &str) -> Result<Entry, Error> {
+    let (_rest, (patterns, outputs)) =
+        all_consuming(parse_inputs_and_outputs)(text).map_err(|error| match error {
+            nom::Err::Error(error) | nom::Err::Failure(error) => syntax_error(line, text, error),
+            nom::Err::Incomplete(_) => unreachable!("complete parsers never ask for more input"),
+        })?;
+    if patterns.len() != DIGITS.len() {
+        return Err(Error::PatternCount {
+            line,
+            count: patterns.len(),
+        });
+    }
+    Ok((patterns, outputs))
 }
 
+/// The entries of a puzzle input, each with the line it came from, and the
+/// lines set aside because they don't parse.
+#[derive(Debug, Default)]
+pub struct Notes {
+    pub entries: Vec<(usize, Entry)>,
+    pub rejected: Vec<Error>,
+}
+
+/// Parses every line into an entry numbered by its line, setting aside the
+/// lines that don't parse together with the reason why.
 #[aoc_generator(day8)]
-fn input_parser(input: &str) -> Vec<(Vec<Sequence>, Vec<Sequence>)> {
-    if let Ok(("", pairs)) = parse_all_inputs(input) {
-        pairs
-    } else {
-        panic!()
+pub fn input_parser(input: &str) -> Notes {
+    let (entries, rejected) = input
+        .lines()
+        .enumerate()
+        .map(|(index, text)| parse_entry(index + 1, text).map(|entry| (index + 1, entry)))
+        .partition_result();
+    Notes { entries, rejected }
+}
+
+/// An answer from the lines that parsed, along with the errors for the lines
+/// that were skipped.
+#[derive(Debug)]
+pub struct Report {
+    pub answer: usize,
+    pub rejected: Vec<Error>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.answer)?;
+        if let Some(first) = self.rejected.first() {
+            write!(
+                f,
+                " (skipping lines that don't parse, {} in all, the first being: {first})",
+                self.rejected.len()
+            )?;
+        }
+        Ok(())
     }
 }
 
 #[aoc(day8, part1)]
-fn part1(input: &[(Vec<Sequence>, Vec<Sequence>)]) -> usize {
+pub fn part1(notes: &Notes) -> Report {
     let known = [
         (2, Digit::One),
         (4, Digit::Four),
@@ -95,11 +434,28 @@ fn part1(input: &[(Vec<Sequence>, Vec<Sequence>)]) -> usize {
     ]
     .into_iter()
     .collect();
-    input
-        .iter()
-        .flat_map(|(_left, right)| right)
-        .filter_map(|seq| infer_digit(&seq[..], &known))
-        .count()
+    Report {
+        answer: notes
+            .entries
+            .iter()
+            .flat_map(|(_line, (_left, right))| right)
+            .filter_map(|&pattern| infer_digit(pattern, &known))
+            .count(),
+        rejected: notes.rejected.clone(),
+    }
+}
+
+/// Sums the outputs of every display that parsed.
+#[aoc(day8, part2)]
+pub fn part2(notes: &Notes) -> Result<Report, Error> {
+    Ok(Report {
+        answer: notes
+            .entries
+            .iter()
+            .map(|(line, entry)| decode(entry, *line))
+            .sum::<Result<_, _>>()?,
+        rejected: notes.rejected.clone(),
+    })
 }
 
 #[cfg(test)]
@@ -124,7 +480,7 @@ mod tests {
 
             if let Ok(("", sequence)) = parse_sequence(raw_sequence) {
                 assert_eq!(
-                    infer_digit(&sequence[0], &known),
+                    infer_digit(sequence[0], &known),
                     Some(*known.get(&count).unwrap())
                 );
             } else {
@@ -132,4 +488,201 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_decode() {
+        let (patterns, outputs) = parse_entry(
+            1,
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )
+        .unwrap();
+        assert_eq!(
+            solve_wiring(&patterns, 1).unwrap(),
+            [
+                Segment::C,
+                Segment::F,
+                Segment::G,
+                Segment::A,
+                Segment::B,
+                Segment::D,
+                Segment::E
+            ]
+        );
+        assert_eq!(decode(&(patterns, outputs), 1).unwrap(), 5353);
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = input_parser(
+            r#"
+be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce"#
+                .trim_start(),
+        );
+        assert!(input.rejected.is_empty());
+        assert_eq!(part1(&input).to_string(), "26");
+        assert_eq!(part2(&input).unwrap().answer, 61229);
+    }
+
+    #[test]
+    fn test_inconsistent_and_ambiguous() {
+        let notes = Notes {
+            entries: ["ab abd | ab", "ab abc abcd | ab", "ab ac | ab"]
+                .iter()
+                .map(|text| parse_inputs_and_outputs(text).unwrap().1)
+                .enumerate()
+                .map(|(index, entry)| (index + 1, entry))
+                .collect(),
+            rejected: Vec::new(),
+        };
+        let patterns = |index: usize| &notes.entries[index].1 .0;
+        assert!(matches!(
+            solve_wiring(patterns(0), 1),
+            Err(Error::Ambiguous {
+                line: 1,
+                wirings: 48
+            })
+        ));
+        assert!(matches!(
+            solve_wiring(patterns(1), 2),
+            Err(Error::Inconsistent { line: 2 })
+        ));
+        assert!(matches!(
+            solve_wiring(patterns(2), 3),
+            Err(Error::Inconsistent { line: 3 })
+        ));
+        assert!(matches!(
+            part2(&notes),
+            Err(Error::Ambiguous { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_pattern() {
+        let one = Pattern::from_letters("fc");
+        let seven = Pattern::from_letters("acf");
+        let four = Pattern::from_letters("bcdf");
+        assert_eq!(one.to_string(), "cf");
+        assert_eq!(seven.difference(one), Segment::A.into());
+        assert_eq!(four.intersection(seven), one);
+        assert_eq!(four.union(seven).to_string(), "abcdf");
+        assert_eq!(four.len(), 4);
+        assert_eq!(DIGITS[4].1, four);
+        assert!(Pattern::EMPTY.is_empty());
+        assert_eq!(Pattern::ALL.segments().collect::<Vec<_>>(), SEGMENTS);
+        assert_eq!(
+            parse_sequence("gcbe ebcg").unwrap().1,
+            [Pattern::from_letters("bceg"); 2]
+        );
+
+        let counts: HashMap<Pattern, usize> = parse_sequence("ab ba abc cab bca")
+            .unwrap()
+            .1
+            .into_iter()
+            .counts();
+        assert_eq!(counts[&Pattern::from_letters("ab")], 2);
+        assert_eq!(counts[&Pattern::from_letters("abc")], 3);
+    }
+
+    #[test]
+    fn test_render() {
+        let eight = render(Pattern::ALL, Pattern::EMPTY);
+        assert_eq!(eight, [" _ ", "|_|", "|_|"]);
+        let one = render(Pattern::from_letters("cf"), Pattern::from_letters("a"));
+        assert_eq!(one, [" ? ", "  |", "  |"]);
+
+        let digits: Vec<_> = ["acdeg", "bcdf", "abdfg"]
+            .map(|letters| (Pattern::from_letters(letters), Pattern::EMPTY))
+            .to_vec();
+        assert_eq!(
+            render_display(&digits),
+            " _       _ \n _| |_| |_ \n|_    |  _|"
+        );
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let (patterns, outputs) = parse_entry(
+            1,
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )
+        .unwrap();
+        assert_eq!(
+            diagnose(&patterns, &outputs, 1).unwrap(),
+            " _   _   _   _ \n|_   _| |_   _|\n _|  _|  _|  _|"
+        );
+
+        // Knowing only one and seven fixes the top and right segments but
+        // leaves the others undecided.
+        let (patterns, outputs) = parse_inputs_and_outputs("ab dab | dab e").unwrap().1;
+        assert_eq!(
+            diagnose(&patterns, &outputs, 1).unwrap(),
+            " _     \n  | ?? \n  | ?? "
+        );
+    }
+
+    #[test]
+    fn test_tolerant_parser() {
+        let example =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+        let notes = input_parser(&format!("{example}\n"));
+        assert_eq!(notes.entries.len(), 1);
+        assert!(notes.rejected.is_empty());
+        assert_eq!(input_parser(&example.to_uppercase()).entries, notes.entries);
+
+        let input = [
+            "ab dab | ab",
+            example,
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab cdfeb",
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb xcadb",
+        ]
+        .join("\n");
+        let notes = input_parser(&input);
+        assert_eq!(notes.entries.len(), 1);
+        assert_eq!(notes.entries[0].0, 2);
+        assert_eq!(
+            notes
+                .rejected
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "Line 1 has 2 patterns instead of ten",
+                "Line 3, column 65: expected \" | \" between patterns and outputs",
+                "Line 4, column 68: expected a pattern of segments a to g",
+            ]
+        );
+        // The good line is still solved, and the rest are reported with it.
+        assert_eq!(part1(&notes).answer, 0);
+        let report = part2(&notes).unwrap();
+        assert_eq!(report.answer, 5353);
+        assert_eq!(
+            report.to_string(),
+            "5353 (skipping lines that don't parse, 3 in all, the first being: \
+             Line 1 has 2 patterns instead of ten)"
+        );
+        assert_eq!(
+            input_parser(&format!("{example} | ab"))
+                .rejected
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["Line 1, column 85: unexpected trailing input"]
+        );
+
+        // Displays are numbered by their lines, counting the skipped ones.
+        let input = format!("ab | ab\n{example}\nab ac bc abc abd acd bcd abcd abce abcde | ab");
+        assert!(matches!(
+            part2(&input_parser(&input)),
+            Err(Error::Inconsistent { line: 3 })
+        ));
+    }
 }
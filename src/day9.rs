@@ -1,101 +1,303 @@
+use crate::grid::{self, Direction, Grid, Point};
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashMap;
+use itertools::Itertools;
 
-type Coord = (isize, isize);
-type Number = usize;
+pub mod hydrology;
+pub mod render;
 
+pub type Number = usize;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Grid(#[from] grid::Error),
+    #[error("Rows can only be padded with a height from 0 to 9, not {0}")]
+    PadHeight(u8),
+}
+
+/// Heights at or above this level are walls between basins.
+const WALL: Number = 9;
+
+/// What to do about rows shorter or longer than the others.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RaggedRows {
+    #[default]
+    Reject,
+    /// Extend short rows to the widest one with cells of this height, which
+    /// must be a digit like every other.
+    Pad(u8),
+}
+
+/// Which cells count as adjacent.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Neighborhood {
+    /// The four orthogonal cells.
+    #[default]
+    VonNeumann,
+    /// The four orthogonal and four diagonal cells.
+    Moore,
+}
+
+impl Neighborhood {
+    fn directions(self) -> &'static [Direction] {
+        use Direction::*;
+        match self {
+            Neighborhood::VonNeumann => &[West, East, North, South],
+            Neighborhood::Moore => &[
+                NorthWest, North, NorthEast, West, East, SouthWest, South, SouthEast,
+            ],
+        }
+    }
+}
+
+/// What lies past the edges of the map.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Edges {
+    /// Nothing: cells on the edge have fewer neighbors.
+    #[default]
+    Bounded,
+    /// The opposite edge, as on a torus.
+    Toroidal,
+}
+
+/// Which flat regions count as low points.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Minima {
+    /// Only single cells whose every neighbor is higher, so a flat lowest
+    /// region of two or more cells is no low point and drains nowhere.
+    #[default]
+    Strict,
+    /// Any connected region of equal height with no lower neighbor, so that
+    /// flat valleys count too.
+    NonStrict,
+}
+
+/// How cells of a heightmap relate to each other.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Rules {
+    pub neighborhood: Neighborhood,
+    pub edges: Edges,
+    pub minima: Minima,
+}
+
+/// A heightmap.
 #[derive(Debug)]
-struct Input {
-    grid: HashMap<Coord, Number>,
-    height: isize,
-    width: isize,
+pub struct Input {
+    heights: Grid<u8>,
+    rules: Rules,
 }
 
-#[aoc_generator(day9)]
-fn input_parser(input: &str) -> Input {
-    let grid: HashMap<Coord, Number> = input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, l)| {
-            l.chars()
-                .enumerate()
-                .map(|(x, c)| {
-                    (
-                        (x.try_into().unwrap(), y.try_into().unwrap()),
-                        Number::try_from(c.to_digit(10).unwrap()).unwrap(),
-                    )
-                })
-                .collect::<Vec<(Coord, Number)>>()
-        })
-        .collect();
-
-    let (max_x, max_y) = grid.keys().fold((0, 0), |(old_x, old_y), (x, y)| {
-        (old_x.max(*x), old_y.max(*y))
-    });
-
-    Input {
-        grid,
-        height: max_y + 1,
-        width: max_x + 1,
+/// The cells that drain into one low point, or into one flat low region whose
+/// first cell in reading order stands in for it. Low regions with no wall
+/// between them share a basin.
+#[derive(Debug, PartialEq)]
+pub struct Basin {
+    pub low_point: Point,
+    pub cells: Vec<Point>,
+}
+
+impl Basin {
+    pub fn size(&self) -> usize {
+        self.cells.len()
     }
 }
 
+/// Parses a heightmap, ignoring blank lines before and after it. Coordinates
+/// in errors count from the first row of the map.
+pub fn parse(input: &str, ragged: RaggedRows) -> Result<Input, Error> {
+    let digit = |c: char| c.to_digit(10).map(|d| u8::try_from(d).unwrap());
+    let heights = match ragged {
+        RaggedRows::Reject => Grid::parse(input, digit)?,
+        RaggedRows::Pad(height) if height > 9 => return Err(Error::PadHeight(height)),
+        RaggedRows::Pad(height) => Grid::parse_padded(input, digit, height)?,
+    };
+    Ok(Input {
+        heights,
+        rules: Rules::default(),
+    })
+}
+
+#[aoc_generator(day9)]
+pub fn input_parser(input: &str) -> Result<Input, Error> {
+    parse(input, RaggedRows::Reject)
+}
+
 impl Input {
-    fn adjacent_cells(&self, x: isize, y: isize) -> Option<Vec<(Coord, Number)>> {
-        let offsets: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-
-        if x >= 0 && x < self.width && y >= 0 && y < self.height {
-            let cells = offsets
-                .into_iter()
-                .filter_map(|(offset_x, offset_y)| {
-                    let new_x = x + offset_x;
-                    let new_y = y + offset_y;
-                    self.height_at_cell(new_x, new_y)
-                        .map(|n| ((new_x, new_y), n))
-                })
-                .collect();
-            Some(cells)
-        } else {
-            None
-        }
+    pub fn with_rules(self, rules: Rules) -> Self {
+        Self { rules, ..self }
     }
 
-    fn height_at_cell(&self, x: isize, y: isize) -> Option<Number> {
-        self.grid.get(&(x, y)).copied()
+    pub fn rules(&self) -> Rules {
+        self.rules
     }
 
-    fn is_low_point(&self, x: isize, y: isize) -> Option<bool> {
-        self.height_at_cell(x, y).map(|height| {
-            self.adjacent_cells(x, y)
-                .unwrap()
-                .iter()
-                .all(|(_coord, other)| other > &height)
-        })
+    pub fn heights(&self) -> &Grid<u8> {
+        &self.heights
     }
 
-    fn sum_low_points_risk(&self) -> Number {
-        self.grid
+    /// The height at a cell, if the map has one there.
+    pub fn get(&self, point: Point) -> Option<u8> {
+        self.heights.get(point).copied()
+    }
+
+    /// Every cell with its height, in reading order.
+    fn cells(&self) -> impl Iterator<Item = (Point, Number)> + '_ {
+        self.heights
             .iter()
-            .filter_map(|((x, y), n)| {
-                if let Some(true) = self.is_low_point(*x, *y) {
-                    Some(n + 1)
-                } else {
-                    None
+            .map(|(point, &height)| (point, height.into()))
+    }
+
+    fn adjacent_cells(&self, point: Point) -> Option<impl Iterator<Item = (Point, Number)> + '_> {
+        self.heights.get(point)?;
+        let directions = self.rules.neighborhood.directions();
+        Some(directions.iter().filter_map(move |&direction| {
+            let neighbor = match self.rules.edges {
+                Edges::Bounded => point + direction,
+                Edges::Toroidal => self.heights.wrap(point + direction)?,
+            };
+            // A map one cell across wraps around onto the cell itself.
+            if neighbor == point {
+                return None;
+            }
+            self.height_at_cell(neighbor).map(|n| (neighbor, n))
+        }))
+    }
+
+    fn height_at_cell(&self, point: Point) -> Option<Number> {
+        self.get(point).map(Number::from)
+    }
+
+    /// Every connected region of equal height, each starting from its first
+    /// cell in reading order, flagged with whether no cell in it has a lower
+    /// neighbor.
+    fn flat_regions(&self) -> Vec<(Vec<Point>, bool)> {
+        let mut seen = self.heights.map(|_| false);
+        let mut regions = Vec::new();
+        for (start, height) in self.cells() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut pending = vec![start];
+            let mut region = Vec::new();
+            let mut lowest = true;
+            while let Some(point) = pending.pop() {
+                region.push(point);
+                for (neighbor, other) in self.adjacent_cells(point).unwrap() {
+                    if other < height {
+                        lowest = false;
+                    } else if other == height && !seen[neighbor] {
+                        seen[neighbor] = true;
+                        pending.push(neighbor);
+                    }
                 }
-            })
+            }
+            region[1..].sort_unstable_by_key(|point| point.reading_order());
+            regions.push((region, lowest));
+        }
+        regions
+    }
+
+    /// Whether a region of equal height with no lower neighbor counts as a
+    /// low point under the map's rules.
+    fn is_minimum(&self, region: &[Point]) -> bool {
+        self.rules.minima == Minima::NonStrict || region.len() == 1
+    }
+
+    /// The regions of equal height with no lower neighbor that count as low
+    /// points under the map's rules, each in reading order.
+    pub fn low_regions(&self) -> Vec<Vec<Point>> {
+        self.flat_regions()
+            .into_iter()
+            .filter(|(region, lowest)| *lowest && self.is_minimum(region))
+            .map(|(region, _lowest)| region)
+            .collect()
+    }
+
+    /// The low points in reading order.
+    pub fn low_points(&self) -> Vec<Point> {
+        self.low_regions()
+            .into_iter()
+            .flatten()
+            .sorted_unstable_by_key(|point| point.reading_order())
+            .collect()
+    }
+
+    /// Flood-fills outwards from a low region, stopping at walls and at cells
+    /// already `seen`, which it marks as it goes.
+    fn basin(&self, region: &[Point], seen: &mut Grid<bool>) -> Basin {
+        for &point in region {
+            seen[point] = true;
+        }
+        let mut pending = region.to_vec();
+        let mut cells = Vec::new();
+        while let Some(point) = pending.pop() {
+            cells.push(point);
+            for (neighbor, height) in self.adjacent_cells(point).unwrap() {
+                if height < WALL && !seen[neighbor] {
+                    seen[neighbor] = true;
+                    pending.push(neighbor);
+                }
+            }
+        }
+        cells.sort_unstable_by_key(|point| point.reading_order());
+        Basin {
+            low_point: region[0],
+            cells,
+        }
+    }
+
+    /// The basin around each low region, in the order of their first cells.
+    /// Low regions that share an area bounded by walls share one basin, named
+    /// after the first of them, so every cell is in at most one basin.
+    pub fn basins(&self) -> Vec<Basin> {
+        let mut seen = self.heights.map(|_| false);
+        let mut basins = Vec::new();
+        for region in self.low_regions() {
+            if !seen[region[0]] && self.height_at_cell(region[0]) < Some(WALL) {
+                basins.push(self.basin(&region, &mut seen));
+            }
+        }
+        basins
+    }
+
+    /// The product of the sizes of the three largest basins.
+    pub fn largest_basins_product(&self) -> usize {
+        self.basins()
+            .iter()
+            .map(Basin::size)
+            .sorted_unstable_by(|a, b| b.cmp(a))
+            .take(3)
+            .product()
+    }
+
+    fn sum_low_points_risk(&self) -> Number {
+        self.low_points()
+            .into_iter()
+            .filter_map(|point| self.height_at_cell(point))
+            .map(|n| n + 1)
             .sum()
     }
 }
 
 #[aoc(day9, part1)]
-fn calculate_risk_sum(input: &Input) -> Number {
+pub(crate) fn calculate_risk_sum(input: &Input) -> Number {
     input.sum_low_points_risk()
 }
 
+#[aoc(day9, part2)]
+pub(crate) fn largest_basins_product(input: &Input) -> usize {
+    input.largest_basins_product()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn points(coords: &[(isize, isize)]) -> Vec<Point> {
+        coords.iter().copied().map(Point::from).collect()
+    }
+
     #[test]
     fn test_sum_low_points_risk() {
         let raw_input = r#"
@@ -105,7 +307,110 @@ mod tests {
 8767896789
 9899965678
 "#;
-        let input = input_parser(raw_input);
+        let input = input_parser(raw_input).unwrap();
         assert_eq!(input.sum_low_points_risk(), 15);
     }
+
+    #[test]
+    fn test_basins() {
+        let raw_input = r#"
+2199943210
+3987894921
+9856789892
+8767896789
+9899965678
+"#;
+        let input = input_parser(raw_input).unwrap();
+        let basins = input.basins();
+        assert_eq!(
+            basins
+                .iter()
+                .map(|b| ((b.low_point.x, b.low_point.y), b.size()))
+                .collect::<Vec<_>>(),
+            [((1, 0), 3), ((9, 0), 9), ((2, 2), 14), ((6, 4), 9)]
+        );
+        assert_eq!(basins[0].cells, points(&[(0, 0), (1, 0), (0, 1)]));
+        assert_eq!(input.largest_basins_product(), 1134);
+    }
+
+    #[test]
+    fn test_large_basin() {
+        // A single basin far larger than the call stack could recurse over.
+        let raw_input = vec!["1".repeat(300); 300].join("\n").replacen('1', "0", 1);
+        let input = input_parser(&raw_input).unwrap();
+        let basins = input.basins();
+        assert_eq!(basins.len(), 1);
+        assert_eq!(basins[0].size(), 90_000);
+
+        // Low points with no wall between them share one basin.
+        let input = input_parser("0120\n9999\n3219").unwrap();
+        let basins = input.basins();
+        assert_eq!(
+            basins
+                .iter()
+                .map(|b| ((b.low_point.x, b.low_point.y), b.size()))
+                .collect::<Vec<_>>(),
+            [((0, 0), 4), ((2, 2), 3)]
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let input = input_parser("9999\n9119\n9999").unwrap();
+        assert!(input.low_points().is_empty());
+        let input = input.with_rules(Rules {
+            minima: Minima::NonStrict,
+            ..Rules::default()
+        });
+        assert_eq!(input.low_regions(), [points(&[(1, 1), (2, 1)])]);
+        assert_eq!(input.basins()[0].size(), 2);
+
+        let input = input_parser("19\n92").unwrap();
+        assert_eq!(input.low_points(), points(&[(0, 0), (1, 1)]));
+        let input = input.with_rules(Rules {
+            neighborhood: Neighborhood::Moore,
+            ..Rules::default()
+        });
+        assert_eq!(input.low_points(), points(&[(0, 0)]));
+
+        let input = input_parser("05951").unwrap();
+        assert_eq!(input.low_points(), points(&[(0, 0), (4, 0)]));
+        let input = input.with_rules(Rules {
+            edges: Edges::Toroidal,
+            ..Rules::default()
+        });
+        assert_eq!(input.low_points(), points(&[(0, 0)]));
+        assert_eq!(
+            input.basins()[0].cells,
+            points(&[(0, 0), (1, 0), (3, 0), (4, 0)])
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = input_parser("\n\n219\r\n398\n\n").unwrap();
+        assert_eq!((input.heights.width(), input.heights.height()), (3, 2));
+        assert_eq!(input.get(Point::new(2, 1)), Some(8));
+
+        assert!(matches!(
+            input_parser("219\n39\n987"),
+            Err(Error::Grid(grid::Error::Ragged {
+                y: 1,
+                expected: 3,
+                found: 2
+            }))
+        ));
+        let padded = parse("219\n3\n987", RaggedRows::Pad(9)).unwrap();
+        assert_eq!(padded.heights.to_string(), "219\n399\n987");
+        assert!(matches!(
+            parse("219\n3\n987", RaggedRows::Pad(10)),
+            Err(Error::PadHeight(10))
+        ));
+
+        assert_eq!(
+            input_parser("219\n3x8").unwrap_err().to_string(),
+            "Unexpected 'x' at cell (1, 1)"
+        );
+        assert!(input_parser("\n\n").unwrap().low_points().is_empty());
+    }
 }
@@ -0,0 +1,201 @@
+//! Where water, or smoke, flows on a heightmap: each cell drains to its lowest
+//! lower neighbor until it reaches a sink with no lower neighbor at all. Flat
+//! regions of equal height drain together, as a single cell would.
+
+use super::Input;
+use crate::grid::{Grid, Point};
+
+impl Input {
+    /// The lowest neighbor strictly below a cell, favoring the first in
+    /// neighborhood order on ties, or `None` for a sink or a cell off the map.
+    pub fn steepest_descent(&self, point: Point) -> Option<Point> {
+        let height = self.height_at_cell(point)?;
+        self.adjacent_cells(point)?
+            .filter(|&(_point, other)| other < height)
+            .min_by_key(|&(_point, other)| other)
+            .map(|(point, _height)| point)
+    }
+
+    /// The cells visited flowing downhill from a cell, ending at the first
+    /// with no lower neighbor.
+    pub fn flow_path(&self, start: Point) -> Vec<Point> {
+        let mut path = vec![start];
+        while let Some(next) = self.steepest_descent(*path.last().unwrap()) {
+            path.push(next);
+        }
+        path
+    }
+
+    /// Labels every cell with the sink it drains to. The sinks are the low
+    /// regions of the map's rules; a flat region drains through the lowest
+    /// cell next to any of its cells, and one with nothing lower that is not
+    /// a low point under the rules drains nowhere. Under [`Minima::Strict`](super::Minima::Strict)
+    /// that includes any flat lowest region of two or more cells, so it and
+    /// everything draining into it get no label.
+    pub fn drainage(&self) -> Drainage<'_> {
+        let regions = self.flat_regions();
+        let mut region_of = self.heights.map(|_| 0);
+        for (index, (region, _lowest)) in regions.iter().enumerate() {
+            for &point in region {
+                region_of[point] = index;
+            }
+        }
+
+        let mut sinks = Vec::new();
+        // Where each region drains: `None` while unresolved, then the sink's
+        // label if it has one.
+        let mut labels: Vec<Option<Option<usize>>> = vec![None; regions.len()];
+        let mut outlets = Vec::with_capacity(regions.len());
+        for (index, (region, lowest)) in regions.iter().enumerate() {
+            let outlet = region
+                .iter()
+                .filter_map(|&point| self.steepest_descent(point))
+                .min_by_key(|&point| self.height_at_cell(point));
+            outlets.push(outlet.map(|point| region_of[point]));
+            if *lowest {
+                labels[index] = Some(self.is_minimum(region).then(|| {
+                    sinks.push(region[0]);
+                    sinks.len() - 1
+                }));
+            }
+        }
+        // Follow each unresolved region downhill until the flow joins a
+        // resolved one, then resolve the whole stretch after it.
+        for start in 0..regions.len() {
+            let mut path = Vec::new();
+            let mut current = start;
+            let label = loop {
+                if let Some(label) = labels[current] {
+                    break label;
+                }
+                path.push(current);
+                current = outlets[current].unwrap();
+            };
+            for index in path {
+                labels[index] = Some(label);
+            }
+        }
+
+        Drainage {
+            input: self,
+            sinks,
+            labels: region_of.map(|&index| labels[index].unwrap()),
+        }
+    }
+}
+
+/// The sink each cell of a heightmap drains to.
+#[derive(Debug)]
+pub struct Drainage<'a> {
+    input: &'a Input,
+    sinks: Vec<Point>,
+    labels: Grid<Option<usize>>,
+}
+
+impl Drainage<'_> {
+    /// The sinks in reading order, each flat one by its first cell; labels
+    /// index into this.
+    pub fn sinks(&self) -> &[Point] {
+        &self.sinks
+    }
+
+    /// The label of the sink a cell drains to, or `None` off the map and for
+    /// cells that drain nowhere, as into a flat lowest region that is not a
+    /// low point under [`Minima::Strict`](super::Minima::Strict).
+    pub fn label(&self, point: Point) -> Option<usize> {
+        self.labels.get(point).copied().flatten()
+    }
+
+    pub fn sink(&self, point: Point) -> Option<Point> {
+        self.label(point).map(|label| self.sinks[label])
+    }
+
+    /// The cells next to a cell that drains somewhere else, in reading order.
+    pub fn ridges(&self) -> Vec<Point> {
+        self.input
+            .cells()
+            .map(|(point, _height)| point)
+            .filter(|&point| {
+                let label = self.label(point);
+                self.input
+                    .adjacent_cells(point)
+                    .unwrap()
+                    .any(|(other, _height)| self.label(other) != label)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::grid::Point;
+
+    #[test]
+    fn test_flow() {
+        let input =
+            input_parser("2199943210\n3987894921\n9856789892\n8767896789\n9899965678").unwrap();
+        assert_eq!(
+            input.steepest_descent(Point::new(0, 0)),
+            Some(Point::new(1, 0))
+        );
+        assert_eq!(input.steepest_descent(Point::new(1, 0)), None);
+        assert_eq!(
+            input.flow_path(Point::new(4, 2)),
+            [Point::new(4, 2), Point::new(3, 2), Point::new(2, 2)]
+        );
+
+        let drainage = input.drainage();
+        assert_eq!(drainage.sinks(), input.low_points());
+        assert_eq!(drainage.sink(Point::new(0, 4)), Some(Point::new(2, 2)));
+        assert_eq!(drainage.sink(Point::new(9, 4)), Some(Point::new(6, 4)));
+        assert_eq!(drainage.label(Point::new(10, 0)), None);
+    }
+
+    #[test]
+    fn test_ridges() {
+        let input = input_parser("12321\n99999").unwrap();
+        let drainage = input.drainage();
+        assert_eq!(drainage.sinks(), [Point::new(0, 0), Point::new(4, 0)]);
+        assert_eq!(drainage.sink(Point::new(2, 0)), Some(Point::new(0, 0)));
+        // The wall of 9s drains as one, through the first of its lowest
+        // neighbors.
+        assert_eq!(drainage.sink(Point::new(4, 1)), Some(Point::new(0, 0)));
+        assert_eq!(
+            drainage.ridges(),
+            [
+                Point::new(2, 0),
+                Point::new(3, 0),
+                Point::new(4, 0),
+                Point::new(3, 1),
+                Point::new(4, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flat_drainage() {
+        // Per cell, every 9 here and both 1s would be sinks of their own.
+        let input = input_parser("9999\n9119\n9999").unwrap();
+        let drainage = input.drainage();
+        assert!(drainage.sinks().is_empty());
+        assert_eq!(drainage.sink(Point::new(0, 0)), None);
+        assert_eq!(drainage.label(Point::new(1, 1)), None);
+
+        let input = input.with_rules(Rules {
+            minima: Minima::NonStrict,
+            ..Rules::default()
+        });
+        let drainage = input.drainage();
+        assert_eq!(drainage.sinks(), [Point::new(1, 1)]);
+        assert_eq!(drainage.sink(Point::new(3, 2)), Some(Point::new(1, 1)));
+        assert_eq!(drainage.sink(Point::new(2, 1)), Some(Point::new(1, 1)));
+        assert!(drainage.ridges().is_empty());
+
+        let input = input_parser(
+            "2199943210\n3987894921\n9856789892\n8767896789\n9899965678\n8888888888\n8888888888",
+        )
+        .unwrap();
+        assert_eq!(input.drainage().sinks(), input.low_points());
+    }
+}
@@ -0,0 +1,142 @@
+//! Pictures of a heightmap, for checking basins by eye.
+
+use super::Input;
+use crate::grid::{Grid, Point};
+use std::io::{self, Write};
+
+const RESET: &str = "\x1b[0m";
+/// Bold red, for low points.
+const LOW_POINT: &str = "\x1b[1;31m";
+/// Yellow, for basin cells next to something outside their basin.
+const BOUNDARY: &str = "\x1b[33m";
+
+/// A color for each basin, spreading hues by the golden ratio so that
+/// neighboring basins rarely look alike.
+fn basin_color(basin: usize) -> [u8; 3] {
+    let hue = (basin as f64 * 0.618_033_988_75).fract() * 6.0;
+    let (saturation, value) = (0.65, 0.95);
+    let chroma = value * saturation;
+    let second = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, second, 0.0),
+        1 => (second, chroma, 0.0),
+        2 => (0.0, chroma, second),
+        3 => (0.0, second, chroma),
+        4 => (second, 0.0, chroma),
+        _ => (chroma, 0.0, second),
+    };
+    let lightness = value - chroma;
+    [r, g, b].map(|channel| ((channel + lightness) * 255.0).round() as u8)
+}
+
+impl Input {
+    /// The basin each cell belongs to, by its position in `basins()`.
+    fn basin_labels(&self) -> Grid<Option<usize>> {
+        let mut labels = self.heights.map(|_| None);
+        for (label, basin) in self.basins().iter().enumerate() {
+            for &point in &basin.cells {
+                labels[point] = Some(label);
+            }
+        }
+        labels
+    }
+
+    fn is_boundary(&self, labels: &Grid<Option<usize>>, point: Point) -> bool {
+        let label = labels[point];
+        label.is_some()
+            && self
+                .adjacent_cells(point)
+                .unwrap()
+                .any(|(neighbor, _height)| labels[neighbor] != label)
+    }
+
+    /// Draws the heightmap for a terminal, one digit per cell on a grey
+    /// background that lightens with height, with low points and basin
+    /// boundaries picked out in color.
+    pub fn render_ansi(&self) -> String {
+        let labels = self.basin_labels();
+        let low_points = self.low_points();
+        let mut out = String::new();
+        for (point, height) in self.cells() {
+            // The 24 greys of the 256-color palette start at 232.
+            let grey = 232 + height * 23 / 9;
+            let highlight = if low_points
+                .binary_search_by_key(&point.reading_order(), |low_point| {
+                    low_point.reading_order()
+                })
+                .is_ok()
+            {
+                LOW_POINT
+            } else if self.is_boundary(&labels, point) {
+                BOUNDARY
+            } else {
+                ""
+            };
+            out += &format!("\x1b[48;5;{grey}m{highlight}{height}{RESET}");
+            if usize::try_from(point.x + 1) == Ok(self.heights.width()) {
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Writes the heightmap as a binary PPM image with one pixel per cell,
+    /// each basin in its own color and everything else in grey by height.
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let labels = self.basin_labels();
+        write!(
+            writer,
+            "P6\n{} {}\n255\n",
+            self.heights.width(),
+            self.heights.height()
+        )?;
+        for (label, &height) in labels.values().zip(self.heights.values()) {
+            let pixel = match label {
+                Some(basin) => basin_color(*basin),
+                None => [u8::try_from(u16::from(height) * 255 / 18).unwrap(); 3],
+            };
+            writer.write_all(&pixel)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    #[test]
+    fn test_render_ansi() {
+        let input = input_parser("919\n999").unwrap();
+        let rendered = input.render_ansi();
+        assert_eq!(rendered.lines().count(), 2);
+        assert_eq!(
+            rendered.lines().next().unwrap(),
+            format!(
+                "\x1b[48;5;255m9{RESET}\x1b[48;5;234m{LOW_POINT}1{RESET}\x1b[48;5;255m9{RESET}"
+            )
+        );
+
+        let input = input_parser("1239\n2349\n9999").unwrap();
+        let rendered = input.render_ansi();
+        let top = rendered.lines().next().unwrap();
+        assert!(top.contains(&format!("{BOUNDARY}3")));
+        assert!(!top.contains(&format!("{BOUNDARY}2")));
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let input = input_parser("19\n93").unwrap();
+        let mut image = Vec::new();
+        input.write_ppm(&mut image).unwrap();
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(&image[..header.len()], header);
+        let pixels = &image[header.len()..];
+        assert_eq!(pixels.len(), 4 * 3);
+        assert_eq!(pixels[0..3], basin_color(0));
+        assert_eq!(pixels[9..12], basin_color(1));
+        assert_eq!(pixels[3..6], [127; 3]);
+        assert_ne!(basin_color(0), basin_color(1));
+    }
+}
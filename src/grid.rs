@@ -0,0 +1,467 @@
+use std::{
+    fmt,
+    iter::StepBy,
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign},
+    slice::{self, ChunksExact},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Row {y} has {found} cells where the map is {expected} wide")]
+    Ragged {
+        y: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Unexpected {character:?} at cell ({x}, {y})")]
+    BadCharacter { x: usize, y: usize, character: char },
+    #[error("{cells} cells don't fill rows of {width}")]
+    Shape { width: usize, cells: usize },
+}
+
+/// A position on a grid, with `y` growing downwards.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub const ORIGIN: Self = Self::new(0, 0);
+
+    pub const fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan(self, other: Self) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// The key that sorts points row by row, left to right.
+    pub fn reading_order(self) -> (isize, isize) {
+        (self.y, self.x)
+    }
+}
+
+impl From<(isize, isize)> for Point {
+    fn from((x, y): (isize, isize)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl Add for Point {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Point {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<isize> for Point {
+    type Output = Self;
+
+    fn mul(self, rhs: isize) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Neg for Point {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Add<Direction> for Point {
+    type Output = Self;
+
+    fn add(self, rhs: Direction) -> Self::Output {
+        self + rhs.offset()
+    }
+}
+
+/// A step to one of the eight surrounding cells.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ORTHOGONAL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+    pub const ALL: [Self; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+
+    pub const fn offset(self) -> Point {
+        match self {
+            Self::North => Point::new(0, -1),
+            Self::NorthEast => Point::new(1, -1),
+            Self::East => Point::new(1, 0),
+            Self::SouthEast => Point::new(1, 1),
+            Self::South => Point::new(0, 1),
+            Self::SouthWest => Point::new(-1, 1),
+            Self::West => Point::new(-1, 0),
+            Self::NorthWest => Point::new(-1, -1),
+        }
+    }
+
+    pub const fn opposite(self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::NorthEast => Self::SouthWest,
+            Self::East => Self::West,
+            Self::SouthEast => Self::NorthWest,
+            Self::South => Self::North,
+            Self::SouthWest => Self::NorthEast,
+            Self::West => Self::East,
+            Self::NorthWest => Self::SouthEast,
+        }
+    }
+}
+
+/// A rectangle of cells stored row by row.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Parses one cell per character, ignoring blank lines before and after
+    /// the grid. Coordinates in errors count from its first row.
+    pub fn parse(text: &str, cell: impl Fn(char) -> Option<T>) -> Result<Self, Error> {
+        Self::parse_rows(text, cell, None)
+    }
+
+    /// Parses like [`Grid::parse`], but extends short rows to the widest one
+    /// with `fill` instead of rejecting them.
+    pub fn parse_padded(
+        text: &str,
+        cell: impl Fn(char) -> Option<T>,
+        fill: T,
+    ) -> Result<Self, Error> {
+        Self::parse_rows(text, cell, Some(fill))
+    }
+
+    fn parse_rows(
+        text: &str,
+        cell: impl Fn(char) -> Option<T>,
+        fill: Option<T>,
+    ) -> Result<Self, Error> {
+        let rows: Vec<&str> = text
+            .lines()
+            .skip_while(|row| row.trim().is_empty())
+            .collect();
+        let rows = match rows.iter().rposition(|row| !row.trim().is_empty()) {
+            Some(last) => &rows[..=last],
+            None => &[][..],
+        };
+
+        let widths = rows.iter().map(|row| row.chars().count());
+        let width = match fill {
+            None => {
+                let expected = widths.clone().next().unwrap_or(0);
+                if let Some((y, found)) = widths.enumerate().find(|&(_y, found)| found != expected)
+                {
+                    return Err(Error::Ragged { y, expected, found });
+                }
+                expected
+            }
+            Some(_) => widths.max().unwrap_or(0),
+        };
+
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, character) in row.chars().enumerate() {
+                cells.push(cell(character).ok_or(Error::BadCharacter { x, y, character })?);
+            }
+            if let Some(fill) = &fill {
+                cells.resize((y + 1) * width, fill.clone());
+            }
+        }
+
+        Ok(Self {
+            width,
+            height: rows.len(),
+            cells,
+        })
+    }
+}
+
+impl<T> Grid<T> {
+    /// Cuts cells listed row by row into rows of `width`.
+    pub fn from_vec(width: usize, cells: Vec<T>) -> Result<Self, Error> {
+        if width == 0 && cells.is_empty() {
+            return Ok(Self {
+                width,
+                height: 0,
+                cells,
+            });
+        }
+        if width == 0 || cells.len() % width != 0 {
+            return Err(Error::Shape {
+                width,
+                cells: cells.len(),
+            });
+        }
+        Ok(Self {
+            width,
+            height: cells.len() / width,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.index_of(point).is_some()
+    }
+
+    /// The position of a point in row-by-row order, if it is on the grid.
+    pub fn index_of(&self, Point { x, y }: Point) -> Option<usize> {
+        let x = usize::try_from(x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(y).ok().filter(|&y| y < self.height)?;
+        Some(y * self.width + x)
+    }
+
+    pub fn point_at(&self, index: usize) -> Point {
+        Point::new(
+            (index % self.width).try_into().unwrap(),
+            (index / self.width).try_into().unwrap(),
+        )
+    }
+
+    /// The point on the grid reached by wrapping around its edges, or `None`
+    /// on an empty grid, where there is none.
+    pub fn wrap(&self, Point { x, y }: Point) -> Option<Point> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(Point::new(
+            x.rem_euclid(self.width.try_into().unwrap()),
+            y.rem_euclid(self.height.try_into().unwrap()),
+        ))
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.index_of(point).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.index_of(point).map(|index| &mut self.cells[index])
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (self.point_at(index), cell))
+    }
+
+    pub fn values(&self) -> slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    /// The cells one step from a point in each of the given directions that
+    /// lie on the grid.
+    pub fn neighbors<'a>(
+        &'a self,
+        point: Point,
+        directions: &'a [Direction],
+    ) -> impl Iterator<Item = (Point, &'a T)> + 'a {
+        directions.iter().filter_map(move |&direction| {
+            let neighbor = point + direction;
+            self.get(neighbor).map(|cell| (neighbor, cell))
+        })
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        (y < self.height).then(|| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn rows(&self) -> ChunksExact<'_, T> {
+        self.cells.chunks_exact(self.width.max(1))
+    }
+
+    /// The cells of column `x` from top to bottom, none at all if the grid
+    /// has no rows.
+    pub fn column(&self, x: usize) -> Option<StepBy<slice::Iter<'_, T>>> {
+        (x < self.width).then(|| {
+            self.cells
+                .get(x..)
+                .unwrap_or_default()
+                .iter()
+                .step_by(self.width)
+        })
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = StepBy<slice::Iter<'_, T>>> + '_ {
+        (0..self.width).map(|x| self.column(x).unwrap())
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &Self::Output {
+        self.get(point)
+            .unwrap_or_else(|| panic!("{point} is off the grid"))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut Self::Output {
+        self.get_mut(point)
+            .unwrap_or_else(|| panic!("{point} is off the grid"))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{cell}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point() {
+        let p = Point::new(3, -2);
+        assert_eq!(p + Point::new(1, 1), Point::new(4, -1));
+        assert_eq!(p - Point::new(1, 1), Point::new(2, -3));
+        assert_eq!(-p * 2, Point::new(-6, 4));
+        assert_eq!(p + Direction::North, Point::new(3, -3));
+        assert_eq!(p.manhattan(Point::ORIGIN), 5);
+        assert!(Direction::ALL
+            .iter()
+            .all(|&d| d.offset() + d.opposite().offset() == Point::ORIGIN));
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::parse("\n123\n456\n", |c| c.to_digit(10)).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[Point::new(2, 1)], 6);
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.row(1), Some(&[4, 5, 6][..]));
+        assert_eq!(grid.column(1).unwrap().copied().collect::<Vec<_>>(), [2, 5]);
+        assert_eq!(grid.columns().count(), 3);
+        assert_eq!(
+            grid.neighbors(Point::new(0, 0), &Direction::ORTHOGONAL)
+                .map(|(point, &cell)| (point, cell))
+                .collect::<Vec<_>>(),
+            [(Point::new(1, 0), 2), (Point::new(0, 1), 4)]
+        );
+        assert_eq!(grid.wrap(Point::new(-1, 2)), Some(Point::new(2, 0)));
+        assert_eq!(grid.to_string(), "123\n456");
+
+        assert!(matches!(
+            Grid::parse("12\n3", |c| c.to_digit(10)),
+            Err(Error::Ragged { y: 1, .. })
+        ));
+        let padded = Grid::parse_padded("12\n3", |c| c.to_digit(10), 0).unwrap();
+        assert_eq!(padded.to_string(), "12\n30");
+        assert!(matches!(
+            Grid::from_vec(2, vec![1, 2, 3]),
+            Err(Error::Shape { width: 2, cells: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_empty_grid() {
+        for grid in [
+            Grid::from_vec(3, Vec::<u8>::new()).unwrap(),
+            Grid::new(3, 0, 0),
+            Grid::new(0, 3, 0),
+        ] {
+            assert!(grid.is_empty());
+            assert_eq!(grid.wrap(Point::new(1, 1)), None);
+            assert_eq!(grid.columns().count(), grid.width());
+            assert!(grid.columns().all(|mut column| column.next().is_none()));
+            assert_eq!(grid.rows().count(), 0);
+        }
+    }
+}
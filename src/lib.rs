@@ -1,13 +1,288 @@
 use aoc_runner_derive::aoc_lib;
+use std::{fmt, str::FromStr};
 
 mod day1;
 mod day2;
 mod day3;
 mod day4;
 mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod grid;
+pub mod wiring;
 
 aoc_lib! { year = 2021 }
+
+/// What any solution answers with.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Number(u128),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{n}"),
+            Answer::Text(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+macro_rules! answer_from_unsigned {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(n: $t) -> Self {
+                Answer::Number(n.into())
+            }
+        })*
+    };
+}
+
+answer_from_unsigned!(u8, u16, u32, u64, u128);
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Number(n.try_into().unwrap())
+    }
+}
+
+impl From<String> for Answer {
+    fn from(text: String) -> Self {
+        Answer::Text(text)
+    }
+}
+
+/// A day8 answer is only a number when every line was solved; otherwise it
+/// names the lines it skipped.
+impl From<day8::Report> for Answer {
+    fn from(report: day8::Report) -> Self {
+        if report.rejected.is_empty() {
+            report.answer.into()
+        } else {
+            report.to_string().into()
+        }
+    }
+}
+
+/// Names one solution, written `day6/part2` or `day6/part2/matrix` for an
+/// alternative implementation.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SolutionId {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<String>,
+}
+
+impl SolutionId {
+    pub fn new(day: u8, part: u8) -> Self {
+        Self {
+            day,
+            part,
+            variant: None,
+        }
+    }
+
+    pub fn with_variant(self, variant: &str) -> Self {
+        Self {
+            variant: Some(variant.to_string()),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for SolutionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day{}/part{}", self.day, self.part)?;
+        if let Some(variant) = &self.variant {
+            write!(f, "/{variant}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SolutionId {
+    type Err = anyhow::Error;
+
+    /// Accepts `day6/part2/matrix` as well as the shorter `6/2/matrix`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pieces = s.split('/');
+        let mut number = |prefix: &str| -> anyhow::Result<u8> {
+            let piece = pieces
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Missing {prefix} in solution id {s:?}"))?;
+            let digits = piece.strip_prefix(prefix).unwrap_or(piece);
+            digits
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Bad {prefix} {piece:?} in solution id {s:?}"))
+        };
+        let id = Self::new(number("day")?, number("part")?);
+        match (pieces.next(), pieces.next()) {
+            (None, _) => Ok(id),
+            (Some(variant), None) if !variant.is_empty() => Ok(id.with_variant(variant)),
+            _ => Err(anyhow::anyhow!("Malformed solution id {s:?}")),
+        }
+    }
+}
+
+/// One runnable solution: it parses the puzzle input and answers the part.
+pub struct Solution {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<&'static str>,
+    run: fn(&str) -> anyhow::Result<Answer>,
+}
+
+impl Solution {
+    pub fn id(&self) -> SolutionId {
+        let id = SolutionId::new(self.day, self.part);
+        match self.variant {
+            Some(variant) => id.with_variant(variant),
+            None => id,
+        }
+    }
+
+    /// Solves the part for `input`, ignoring trailing whitespace as
+    /// `cargo aoc` does.
+    pub fn run(&self, input: &str) -> anyhow::Result<Answer> {
+        (self.run)(input.trim_end())
+    }
+}
+
+const fn solution(
+    day: u8,
+    part: u8,
+    variant: Option<&'static str>,
+    run: fn(&str) -> anyhow::Result<Answer>,
+) -> Solution {
+    Solution {
+        day,
+        part,
+        variant,
+        run,
+    }
+}
+
+static SOLUTIONS: [Solution; 19] = [
+    solution(1, 1, None, |input| {
+        Ok(day1::windows(&day1::input_generator(input)?).into())
+    }),
+    solution(1, 2, None, |input| {
+        Ok(day1::nested_windows(&day1::input_generator(input)?).into())
+    }),
+    solution(2, 1, None, |input| {
+        Ok(day2::sum(&day2::parse_instructions(input)?).into())
+    }),
+    solution(2, 2, None, |input| {
+        Ok(day2::sum_with_aim(&day2::parse_instructions(input)?).into())
+    }),
+    solution(3, 1, None, |input| {
+        Ok(day3::part_1(&day3::parse_input(input)?).into())
+    }),
+    solution(3, 2, None, |input| {
+        Ok(day3::part_2(&day3::parse_input(input)?).into())
+    }),
+    solution(4, 1, None, |input| {
+        Ok(day4::find_winning_score(&day4::parse_boards_and_drawings(input)?)?.into())
+    }),
+    solution(4, 2, None, |input| {
+        Ok(day4::find_last_winner(&day4::parse_boards_and_drawings(input)?)?.into())
+    }),
+    solution(5, 1, None, |input| {
+        Ok(day5::part1(&day5::input_generator(input)?).into())
+    }),
+    solution(5, 2, None, |input| {
+        Ok(day5::part2(&day5::input_generator(input)?).into())
+    }),
+    solution(6, 1, None, |input| {
+        Ok(day6::part1(&day6::input_generator(input)?)?.into())
+    }),
+    solution(6, 2, None, |input| {
+        Ok(day6::part2(&day6::input_generator(input)?)?.into())
+    }),
+    solution(6, 2, Some("matrix"), |input| {
+        Ok(day6::part2_matrix(&day6::input_generator(input)?)?.into())
+    }),
+    solution(7, 1, None, |input| {
+        Ok(day7::part1(&day7::input_parser(input)?)?.into())
+    }),
+    solution(7, 2, None, |input| {
+        Ok(day7::part2(&day7::input_parser(input)?)?.into())
+    }),
+    solution(8, 1, None, |input| {
+        Ok(day8::part1(&day8::input_parser(input)).into())
+    }),
+    solution(8, 2, None, |input| {
+        Ok(day8::part2(&day8::input_parser(input))?.into())
+    }),
+    solution(9, 1, None, |input| {
+        Ok(day9::calculate_risk_sum(&day9::input_parser(input)?).into())
+    }),
+    solution(9, 2, None, |input| {
+        Ok(day9::largest_basins_product(&day9::input_parser(input)?).into())
+    }),
+];
+
+/// Every implemented solution, by day, then part, then variant.
+pub fn solutions() -> &'static [Solution] {
+    &SOLUTIONS
+}
+
+pub fn find(id: &SolutionId) -> Option<&'static Solution> {
+    SOLUTIONS.iter().find(|solution| solution.id() == *id)
+}
+
+/// Runs the solution named by `id` on `input`.
+pub fn run(id: &SolutionId, input: &str) -> anyhow::Result<Answer> {
+    find(id)
+        .ok_or_else(|| anyhow::anyhow!("No solution for {id}"))?
+        .run(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solutions() {
+        let ids: Vec<String> = solutions().iter().map(|s| s.id().to_string()).collect();
+        assert_eq!(ids.len(), 19);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.contains(&"day6/part2/matrix".to_string()));
+
+        let id: SolutionId = "day7/part2".parse().unwrap();
+        assert_eq!(
+            run(&id, "16,1,2,0,4,2,7,1,2,14\n").unwrap(),
+            Answer::Number(168)
+        );
+        let id: SolutionId = "6/2/matrix".parse().unwrap();
+        assert_eq!(run(&id, "3,4,3,1,2").unwrap().to_string(), "26984457539");
+
+        assert!(run(&SolutionId::new(25, 1), "").is_err());
+        assert!(run(&SolutionId::new(5, 1), "0,9 -> 5").is_err());
+    }
+
+    #[test]
+    fn test_bad_input() {
+        for (day, input) in [
+            (1, "199\nx"),
+            (2, "forward 5\nsideways 2"),
+            (3, "00100\n2"),
+            (4, "7,4,9\n\n1 2 3"),
+            (7, "16,1,x"),
+        ] {
+            for part in [1, 2] {
+                let id = SolutionId::new(day, part);
+                assert!(run(&id, input).is_err(), "{id} accepted {input:?}");
+            }
+        }
+        // Boards that never win leave no score to report.
+        let input = "1\n\n2 3 4 5 6\n7 8 9 10 11\n12 13 14 15 16\n17 18 19 20 21\n22 23 24 25 26";
+        assert!(run(&SolutionId::new(4, 1), input).is_err());
+        assert!("day6".parse::<SolutionId>().is_err());
+        assert!("day6/partx".parse::<SolutionId>().is_err());
+        assert!("6/2/a/b".parse::<SolutionId>().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use advent_2021::{solutions, Solution};
+use anyhow::{bail, Context};
+use clap::{Parser, Subcommand};
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Runs the Advent of Code 2021 solutions without `cargo aoc`.
+#[derive(Parser)]
+struct Cli {
+    /// Where to find `dayN.txt` when no input is given.
+    #[arg(long, global = true, default_value = "input/2021")]
+    inputs: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run one day, or one part of it.
+    Run {
+        day: u8,
+        part: Option<u8>,
+        /// Run only this alternative implementation.
+        #[arg(long)]
+        variant: Option<String>,
+        /// Read the puzzle input from this file, or from stdin given `-`.
+        #[arg(long, short)]
+        input: Option<PathBuf>,
+    },
+    /// Run every solution on its default input.
+    All,
+    /// Check every solution against the answers recorded in `answers.toml`.
+    Verify,
+    /// List every solution.
+    List,
+}
+
+fn read_input(path: &Path) -> anyhow::Result<String> {
+    if path == Path::new("-") {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("Reading stdin")?;
+        Ok(input)
+    } else {
+        fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))
+    }
+}
+
+fn default_input(inputs: &Path, day: u8) -> PathBuf {
+    inputs.join(format!("day{day}.txt"))
+}
+
+/// Runs each solution in turn, printing its answer and how long it took,
+/// and fails if any of them did.
+fn run_all<'a>(
+    selected: impl IntoIterator<Item = &'a Solution>,
+    input_for: impl Fn(u8) -> anyhow::Result<String>,
+) -> anyhow::Result<()> {
+    let mut failures = 0;
+    let mut total = Duration::ZERO;
+    for solution in selected {
+        let id = solution.id();
+        let result = input_for(solution.day).and_then(|input| {
+            let start = Instant::now();
+            let answer = solution.run(&input)?;
+            Ok((answer, start.elapsed()))
+        });
+        match result {
+            Ok((answer, elapsed)) => {
+                total += elapsed;
+                println!("{id}: {answer} ({elapsed:.2?})");
+            }
+            Err(error) => {
+                failures += 1;
+                println!("{id}: failed: {error:#}");
+            }
+        }
+    }
+    println!("total: {total:.2?}");
+    if failures > 0 {
+        bail!("{failures} solution(s) failed");
+    }
+    Ok(())
+}
+
+/// The recorded answers for each `dayN`, as `partN = answer`.
+fn read_answers(inputs: &Path) -> anyhow::Result<toml::Table> {
+    let path = inputs.join("answers.toml");
+    read_input(&path)?
+        .parse()
+        .with_context(|| format!("Parsing {}", path.display()))
+}
+
+fn expected_answer(answers: &toml::Table, solution: &Solution) -> Option<String> {
+    match answers
+        .get(&format!("day{}", solution.day))?
+        .get(format!("part{}", solution.part))?
+    {
+        toml::Value::Integer(n) => Some(n.to_string()),
+        toml::Value::String(text) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+/// Runs every solution on its default input and prints a table comparing
+/// each answer with the recorded one, failing on any difference.
+fn verify(inputs: &Path) -> anyhow::Result<()> {
+    let answers = read_answers(inputs)?;
+    let mut errors = Vec::new();
+    let mut failures = 0;
+    println!(
+        "{:<20} {:<7} {:>16} {:>16} {:>10}",
+        "solution", "status", "expected", "actual", "time"
+    );
+    for solution in solutions() {
+        let id = solution.id();
+        let expected = expected_answer(&answers, solution);
+        let (result, elapsed) = match read_input(&default_input(inputs, solution.day)) {
+            Ok(input) => {
+                let start = Instant::now();
+                let result = solution.run(&input);
+                (result, start.elapsed())
+            }
+            Err(error) => (Err(error), Duration::ZERO),
+        };
+        let (status, actual) = match (&result, &expected) {
+            (Err(error), _) => {
+                errors.push(format!("{id}: {error:#}"));
+                ("ERROR", "-".to_string())
+            }
+            (Ok(answer), None) => ("MISSING", answer.to_string()),
+            (Ok(answer), Some(expected)) if answer.to_string() == *expected => {
+                ("PASS", answer.to_string())
+            }
+            (Ok(answer), Some(_)) => ("FAIL", answer.to_string()),
+        };
+        if status != "PASS" {
+            failures += 1;
+        }
+        println!(
+            "{:<20} {:<7} {:>16} {:>16} {:>10}",
+            id.to_string(),
+            status,
+            expected.as_deref().unwrap_or("-"),
+            actual,
+            format!("{elapsed:.2?}")
+        );
+    }
+    for error in &errors {
+        eprintln!("{error}");
+    }
+    if failures > 0 {
+        bail!("{failures} of {} solutions did not pass", solutions().len());
+    }
+    println!("all {} solutions pass", solutions().len());
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Run {
+            day,
+            part,
+            variant,
+            input,
+        } => {
+            let selected: Vec<&Solution> = solutions()
+                .iter()
+                .filter(|s| s.day == day)
+                .filter(|s| part.map_or(true, |part| s.part == part))
+                .filter(|s| variant.is_none() || s.variant == variant.as_deref())
+                .collect();
+            if selected.is_empty() {
+                bail!("No solution for day {day} matches");
+            }
+            let input = read_input(&input.unwrap_or_else(|| default_input(&cli.inputs, day)))?;
+            run_all(selected, |_day| Ok(input.clone()))
+        }
+        Command::All => run_all(solutions(), |day| {
+            read_input(&default_input(&cli.inputs, day))
+        }),
+        Command::Verify => verify(&cli.inputs),
+        Command::List => {
+            for solution in solutions() {
+                println!("{}", solution.id());
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_all_counts_failures() {
+        // Every day but day8, which skips the lines it can't parse, rejects
+        // this input; none of them may stop the others from running.
+        let error = run_all(solutions(), |_day| Ok("x\n".to_string())).unwrap_err();
+        assert_eq!(error.to_string(), "17 solution(s) failed");
+
+        let error = run_all(solutions(), |day| {
+            if day == 8 {
+                bail!("No input for day {day}")
+            }
+            Ok("x\n".to_string())
+        })
+        .unwrap_err();
+        assert_eq!(error.to_string(), "19 solution(s) failed");
+    }
+}
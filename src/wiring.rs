@@ -0,0 +1,300 @@
+/// Segment sets are bitmasks over at most this many segments.
+pub const MAX_SEGMENTS: usize = 32;
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Error {
+    #[error("An alphabet can have at most {MAX_SEGMENTS} segments, not {0}")]
+    TooManySegments(usize),
+    #[error("Glyph {index} uses segments beyond the {segments} of its alphabet")]
+    GlyphOutOfRange { index: usize, segments: usize },
+    #[error("Glyphs {first} and {second} light the same segments")]
+    DuplicateGlyph { first: usize, second: usize },
+    #[error("Observation {index} uses wires beyond the {segments} of the display")]
+    ObservationOutOfRange { index: usize, segments: usize },
+}
+
+/// The segments lit for each of the digits 0 to 9 on a seven-segment
+/// display, with segment `a` as the lowest bit and `g` as the highest.
+pub const SEVEN_SEGMENT_DIGITS: [u32; 10] = [
+    0b111_0111, // abcefg
+    0b010_0100, // cf
+    0b101_1101, // acdeg
+    0b110_1101, // acdfg
+    0b010_1110, // bcdf
+    0b110_1011, // abdfg
+    0b111_1011, // abdefg
+    0b010_0101, // acf
+    0b111_1111, // abcdefg
+    0b110_1111, // abcdfg
+];
+
+/// The glyphs a display can show, each a set of lit segments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alphabet {
+    segments: usize,
+    glyphs: Vec<u32>,
+}
+
+impl Alphabet {
+    pub fn new(segments: usize, glyphs: Vec<u32>) -> Result<Self, Error> {
+        if segments > MAX_SEGMENTS {
+            return Err(Error::TooManySegments(segments));
+        }
+        let all = full_mask(segments);
+        if let Some(index) = glyphs.iter().position(|&glyph| glyph & !all != 0) {
+            return Err(Error::GlyphOutOfRange { index, segments });
+        }
+        for (second, glyph) in glyphs.iter().enumerate() {
+            if let Some(first) = glyphs[..second].iter().position(|other| other == glyph) {
+                return Err(Error::DuplicateGlyph { first, second });
+            }
+        }
+        Ok(Self { segments, glyphs })
+    }
+
+    /// The digits 0 to 9 on a seven-segment display, each glyph's index
+    /// being its value.
+    pub fn seven_segment() -> Self {
+        Self {
+            segments: 7,
+            glyphs: SEVEN_SEGMENT_DIGITS.to_vec(),
+        }
+    }
+
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    pub fn glyphs(&self) -> &[u32] {
+        &self.glyphs
+    }
+
+    /// The index of the glyph lighting exactly these segments.
+    pub fn glyph(&self, segments: u32) -> Option<usize> {
+        self.glyphs.iter().position(|&glyph| glyph == segments)
+    }
+}
+
+/// The segment driven by each wire, indexed by wire.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mapping(Vec<usize>);
+
+impl Mapping {
+    pub fn segment(&self, wire: usize) -> usize {
+        self.0[wire]
+    }
+
+    /// The segments lit when these wires are driven.
+    pub fn apply(&self, wires: u32) -> u32 {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|&(wire, _segment)| wires & 1 << wire != 0)
+            .fold(0, |segments, (_wire, segment)| segments | 1 << segment)
+    }
+}
+
+fn full_mask(segments: usize) -> u32 {
+    u32::MAX
+        .checked_shr(u32::try_from(MAX_SEGMENTS - segments).unwrap())
+        .unwrap_or(0)
+}
+
+/// The segments wires of one observation may drive, and those the other wires
+/// may drive, given the glyphs it could be showing.
+fn reach(alphabet: &Alphabet, observation: u32) -> (u32, u32) {
+    let all = full_mask(alphabet.segments);
+    alphabet
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.count_ones() == observation.count_ones())
+        .fold((0, 0), |(inside, outside), glyph| {
+            (inside | glyph, outside | (all & !glyph))
+        })
+}
+
+struct Search<'a> {
+    alphabet: &'a Alphabet,
+    observations: &'a [u32],
+    order: Vec<usize>,
+    candidates: Vec<u32>,
+    assignment: Vec<Option<usize>>,
+    found: Vec<Mapping>,
+}
+
+impl Search<'_> {
+    /// Whether every observation can still show a glyph of the same size that
+    /// covers the segments assigned to its wires and none assigned to others.
+    fn consistent(&self) -> bool {
+        self.observations.iter().all(|&observation| {
+            let (inside, outside) = self.assignment.iter().enumerate().fold(
+                (0, 0),
+                |(inside, outside), (wire, segment)| match segment {
+                    Some(segment) if observation & 1 << wire != 0 => {
+                        (inside | 1 << segment, outside)
+                    }
+                    Some(segment) => (inside, outside | 1 << segment),
+                    None => (inside, outside),
+                },
+            );
+            self.alphabet.glyphs.iter().any(|&glyph| {
+                glyph.count_ones() == observation.count_ones()
+                    && glyph & inside == inside
+                    && glyph & outside == 0
+            })
+        })
+    }
+
+    fn run(&mut self, depth: usize, used: u32) {
+        let Some(&wire) = self.order.get(depth) else {
+            let mapping = Mapping(self.assignment.iter().map(|s| s.unwrap()).collect());
+            self.found.push(mapping);
+            return;
+        };
+        let mut available = self.candidates[wire] & !used;
+        while available != 0 {
+            let segment = available.trailing_zeros() as usize;
+            available &= available - 1;
+            self.assignment[wire] = Some(segment);
+            if self.consistent() {
+                self.run(depth + 1, used | 1 << segment);
+            }
+        }
+        self.assignment[wire] = None;
+    }
+}
+
+/// Every mapping of wires to segments under which each observation, a set of
+/// driven wires, lights a glyph of the alphabet.
+///
+/// Observations that say little leave many mappings, up to every permutation
+/// of the segments when there are none at all.
+pub fn solve(alphabet: &Alphabet, observations: &[u32]) -> Result<Vec<Mapping>, Error> {
+    let segments = alphabet.segments;
+    let all = full_mask(segments);
+    if let Some(index) = observations.iter().position(|&wires| wires & !all != 0) {
+        return Err(Error::ObservationOutOfRange { index, segments });
+    }
+
+    let mut candidates = vec![all; segments];
+    for &observation in observations {
+        let (inside, outside) = reach(alphabet, observation);
+        for (wire, candidate) in candidates.iter_mut().enumerate() {
+            *candidate &= if observation & 1 << wire != 0 {
+                inside
+            } else {
+                outside
+            };
+        }
+    }
+
+    // Pin down the most constrained wires first.
+    let mut order: Vec<usize> = (0..segments).collect();
+    order.sort_by_key(|&wire| candidates[wire].count_ones());
+
+    let mut search = Search {
+        alphabet,
+        observations,
+        order,
+        candidates,
+        assignment: vec![None; segments],
+        found: Vec::new(),
+    };
+    search.run(0, 0);
+    Ok(search.found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(letters: &str) -> u32 {
+        letters.bytes().fold(0, |mask, b| mask | 1 << (b - b'a'))
+    }
+
+    #[test]
+    fn test_seven_segment() {
+        assert_eq!(
+            Alphabet::new(7, SEVEN_SEGMENT_DIGITS.to_vec()).unwrap(),
+            Alphabet::seven_segment()
+        );
+
+        let observations: Vec<u32> = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab"
+            .split(' ')
+            .map(mask)
+            .collect();
+        let mappings = solve(&Alphabet::seven_segment(), &observations).unwrap();
+        assert_eq!(mappings.len(), 1);
+
+        let alphabet = Alphabet::seven_segment();
+        let digits: Vec<_> = ["cdfeb", "fcadb", "cdfeb", "cdbaf"]
+            .iter()
+            .map(|wires| alphabet.glyph(mappings[0].apply(mask(wires))).unwrap())
+            .collect();
+        assert_eq!(digits, [5, 3, 5, 3]);
+    }
+
+    #[test]
+    fn test_ambiguous_and_inconsistent() {
+        let alphabet = Alphabet::seven_segment();
+        assert_eq!(solve(&alphabet, &[]).unwrap().len(), 5040);
+        // Only one and seven are seen, fixing `a` but not which of the
+        // remaining wires is which.
+        assert_eq!(
+            solve(&alphabet, &[mask("ab"), mask("abd")]).unwrap().len(),
+            2 * 24
+        );
+        assert!(solve(&alphabet, &[mask("ab"), mask("ac")])
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            solve(&alphabet, &[mask("h")]),
+            Err(Error::ObservationOutOfRange { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_custom_alphabet() {
+        // A sixteen-segment display showing a few letters, observed through
+        // wires that are shifted by five positions.
+        let glyphs = vec![
+            0b1100_1111_0000_0011,
+            0b0011_1100_1100_0000,
+            0b0000_0011_1111_1100,
+            0b1111_0000_0000_1111,
+            0b0001_0011_0111_1111,
+            0b1010_1010_1010_1010,
+            0b0000_0000_1111_1111,
+            0b1111_1111_0000_0000,
+        ];
+        let alphabet = Alphabet::new(16, glyphs.clone()).unwrap();
+        let scramble = |segments: u32| -> u32 {
+            (0..16)
+                .filter(|segment| segments & 1 << segment != 0)
+                .fold(0, |wires, segment| wires | 1 << ((segment + 5) % 16))
+        };
+        let observations: Vec<u32> = glyphs.iter().map(|&glyph| scramble(glyph)).collect();
+        let mappings = solve(&alphabet, &observations).unwrap();
+        assert_eq!(mappings.len(), 1);
+        assert!((0..16).all(|wire| mappings[0].segment((wire + 5) % 16) == wire));
+
+        assert!(matches!(
+            Alphabet::new(33, vec![]),
+            Err(Error::TooManySegments(33))
+        ));
+        assert!(matches!(
+            Alphabet::new(14, vec![1 << 14]),
+            Err(Error::GlyphOutOfRange {
+                index: 0,
+                segments: 14
+            })
+        ));
+        assert!(matches!(
+            Alphabet::new(14, vec![3, 5, 3]),
+            Err(Error::DuplicateGlyph {
+                first: 0,
+                second: 2
+            })
+        ));
+    }
+}